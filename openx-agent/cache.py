@@ -11,6 +11,8 @@ T = TypeVar("T")
 CACHE_TTL_REPO = 120
 CACHE_TTL_LIST = 60
 CACHE_TTL_PR = 90
+CACHE_TTL_WORKFLOWS = 300
+CACHE_TTL_LOGS = 300
 
 class TTLCache:
     """Thread-safe TTL cache with O(1) LRU eviction."""
@@ -53,6 +55,10 @@ class TTLCache:
         for k in expired:
             del self._data[k]
 
+    def delete(self, key: str) -> None:
+        with self._lock:
+            self._data.pop(key, None)
+
     def clear(self) -> None:
         with self._lock:
             self._data.clear()
@@ -60,6 +66,8 @@ class TTLCache:
 _repo_cache = TTLCache(CACHE_TTL_REPO)
 _list_cache = TTLCache(CACHE_TTL_LIST)
 _pr_cache = TTLCache(CACHE_TTL_PR)
+# Log archives can be megabytes each, so only a handful are kept (for paging).
+_logs_cache = TTLCache(CACHE_TTL_LOGS, max_size=8)
 
 def cached_repo(full_name: str, fetcher: Callable[[], T]) -> T:
     """Return cached repo or call fetcher and cache result."""
@@ -94,8 +102,23 @@ def cached_pr(repo_full_name: str, number: int, fetcher: Callable[[], T]) -> T:
     _pr_cache.set(key, out)
     return out  # type: ignore[return-value]
 
+def cached_logs(repo_full_name: str, run_id: int, fetcher: Callable[[], str]) -> str:
+    """Return cached CI logs for a workflow run or fetch and cache them."""
+    key = f"logs:{repo_full_name}:{run_id}"
+    out = _logs_cache.get(key)
+    if out is not None:
+        return out
+    out = fetcher()
+    _logs_cache.set(key, out)
+    return out
+
+def invalidate_logs(repo_full_name: str, run_id: int) -> None:
+    """Drop cached logs for a run, e.g. once it has been re-run."""
+    _logs_cache.delete(f"logs:{repo_full_name}:{run_id}")
+
 def clear_caches() -> None:
     """Clear all caches (e.g. after long-running write operations)."""
     _repo_cache.clear()
     _list_cache.clear()
     _pr_cache.clear()
+    _logs_cache.clear()
@@ -2,8 +2,65 @@
 
 from __future__ import annotations
 
+import dataclasses
+import logging
 import os
-from dataclasses import dataclass
+import re
+from dataclasses import dataclass, field
+from pathlib import Path
+from typing import Any
+
+try:
+    import tomllib
+except ModuleNotFoundError:  # Python < 3.11
+    import tomli as tomllib  # type: ignore[no-redef]
+
+from . import paths
+
+logger = logging.getLogger(__name__)
+
+PROJECT_CONFIG_NAME = ".openx.toml"
+
+# Top-level .openx.toml keys that override a Settings field.  Credentials and
+# endpoints are deliberately absent: a repository must never be able to
+# redirect the GitHub token to another host or swap in its own credentials.
+_PROJECT_KEYS: dict[str, str] = {
+    "repo": "active_repo",
+    "model": "anthropic_model",
+    "motd": "motd",
+}
+
+# Keys accepted only in the user-level config file (``$XDG_CONFIG_HOME/openx/config.toml``).
+# Their values may use ``${ENV_VAR}`` and ``keyring:<entry>`` references.
+_USER_SECRET_KEYS: dict[str, str] = {
+    "github_base_url": "github_base_url",
+    "github_token": "github_token",
+    "anthropic_api_key": "anthropic_api_key",
+}
+USER_CONFIG_NAME = "config.toml"
+
+# Feature tables allowed in .openx.toml and the keys each accepts.
+PROJECT_TABLES: dict[str, frozenset[str]] = {
+    "sandbox": frozenset({"timeout", "cpu_seconds", "max_output_bytes", "env"}),
+    "heal": frozenset({"policy", "repos"}),
+    "commit": frozenset({"style", "recent"}),
+    "review": frozenset({"checklists", "items"}),
+}
+
+# Feature tables allowed in the user config file.
+USER_TABLES: dict[str, frozenset[str]] = {
+    "retention": frozenset({"history", "cache"}),
+}
+
+# How far github_heal_failing_pr may go, from most to least cautious.
+HEAL_POLICIES = ("analyze-only", "patch-only", "full-auto")
+
+# Built-in lists selectable with ``[review] checklists``.
+REVIEW_CHECKLISTS = ("security", "tests", "docs", "perf")
+
+_ENV_REF_RE = re.compile(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}")
+_KEYRING_PREFIX = "keyring:"
+_KEYRING_SERVICE = "openx"
 
 
 @dataclass(frozen=True)
@@ -14,12 +71,276 @@ class Settings:
     anthropic_model: str = os.getenv("ANTHROPIC_MODEL", "claude-3-opus-latest")
     workspace_root: str = os.getenv("OPENX_WORKSPACE_ROOT", os.getcwd())
     active_repo: str | None = os.getenv("OPENX_ACTIVE_REPO")
+    data_dir: str = os.getenv("OPENX_DATA_DIR") or str(paths.data_dir())
+    motd: str | None = os.getenv("OPENX_MOTD")
+    # Tool results larger than this are saved to a file and truncated (0 disables).
+    max_output_bytes: int = int(os.getenv("OPENX_MAX_OUTPUT_BYTES", str(256 * 1024)))
+    user_config_path: str | None = None
+    # Full parsed user config; secret keys are already interpolated.
+    user: dict[str, Any] = field(default_factory=dict)
+    project_config_path: str | None = None
+    # Full parsed .openx.toml, including tables consumed by individual features.
+    project: dict[str, Any] = field(default_factory=dict)
+
+
+def find_project_config(start: str) -> Path | None:
+    """Return the nearest .openx.toml at or above *start*, or None."""
+    current = Path(start).resolve()
+    for directory in (current, *current.parents):
+        candidate = directory / PROJECT_CONFIG_NAME
+        if candidate.is_file():
+            return candidate
+    return None
+
+
+def _keyring_secret(entry: str) -> str:
+    """Look up *entry* in the OS keychain (service ``openx``); empty if unavailable."""
+    try:
+        import keyring
+    except ImportError:
+        logger.warning("keyring:%s referenced but the keyring package is not installed", entry)
+        return ""
+    try:
+        return keyring.get_password(_KEYRING_SERVICE, entry) or ""
+    except Exception as exc:  # backend errors vary by platform
+        logger.warning("keyring lookup for %s failed: %s", entry, exc)
+        return ""
+
+
+def interpolate(value: Any) -> Any:
+    """Resolve a ``${ENV_VAR}`` or ``keyring:<entry>`` reference in a config value.
+
+    Only applied to the user config's secret keys; unset variables resolve to "".
+    """
+    if not isinstance(value, str):
+        return value
+    if value.startswith(_KEYRING_PREFIX):
+        return _keyring_secret(value[len(_KEYRING_PREFIX):].strip())
+    return _ENV_REF_RE.sub(lambda m: os.environ.get(m.group(1), ""), value)
+
+
+def user_config_path() -> Path:
+    return paths.config_dir() / USER_CONFIG_NAME
+
+
+def _with_user_config(base: Settings) -> Settings:
+    """Merge the user-level config file over *base*.
+
+    This is the only file that may hold credentials or the GitHub API URL,
+    and the only one whose values are interpolated.
+    """
+    path = user_config_path()
+    if not path.is_file():
+        return base
+    try:
+        with open(path, "rb") as fh:
+            data = tomllib.load(fh)
+    except (OSError, tomllib.TOMLDecodeError) as exc:
+        logger.warning("Ignoring %s: %s", path, exc)
+        return base
+    for key in _USER_SECRET_KEYS:
+        if key in data:
+            data[key] = interpolate(data[key])
+    keys = {**_PROJECT_KEYS, **_USER_SECRET_KEYS}
+    overrides: dict[str, Any] = {attr: str(data[key]) for key, attr in keys.items() if data.get(key)}
+    return dataclasses.replace(base, user_config_path=str(path), user=data, **overrides)
+
+
+def _with_project_config(base: Settings) -> Settings:
+    """Merge the project-local .openx.toml over *base* (project values win).
+
+    The file is ignored unless its directory has been trusted with
+    ``openx trust``.
+    """
+    from . import trust
 
+    path = find_project_config(base.workspace_root)
+    if path is None:
+        return base
+    if not trust.is_trusted(path.parent, base.data_dir):
+        logger.warning("Ignoring %s: workspace is not trusted (run 'openx trust %s')", path, path.parent)
+        return base
+    try:
+        with open(path, "rb") as fh:
+            data = tomllib.load(fh)
+    except (OSError, tomllib.TOMLDecodeError) as exc:
+        logger.warning("Ignoring %s: %s", path, exc)
+        return base
+    overrides: dict[str, Any] = {
+        attr: str(data[key]) for key, attr in _PROJECT_KEYS.items() if data.get(key)
+    }
+    return dataclasses.replace(base, project_config_path=str(path), project=data, **overrides)
 
-settings = Settings()
+
+settings = _with_project_config(_with_user_config(Settings()))
 config_settings = settings
 
 
+def _key_line(lines: list[str], key: str, table: str | None = None) -> int | None:
+    """1-based line where *key* is assigned inside *table* (None = top level)."""
+    current: str | None = None
+    key_re = re.compile(rf"^\s*[\"']?{re.escape(key)}[\"']?\s*=")
+    for i, line in enumerate(lines, start=1):
+        header = re.match(r"^\s*\[+\s*([^\]]+?)\s*\]+", line)
+        if header:
+            current = header.group(1)
+            if current == key and table is None:
+                return i
+            continue
+        if current == table and key_re.match(line):
+            return i
+    return None
+
+
+def _check_heal_policies(table: dict[str, Any], where: Any) -> list[tuple[str, str]]:
+    problems: list[tuple[str, str]] = []
+    expected = ", ".join(HEAL_POLICIES)
+    policy = table.get("policy")
+    if policy is not None and policy not in HEAL_POLICIES:
+        problems.append(("error", f"{where('policy', 'heal')}: invalid heal policy {policy!r} (expected one of: {expected})"))
+    repos = table.get("repos", {})
+    if not isinstance(repos, dict):
+        return [*problems, ("error", f"{where('repos', 'heal')}: 'heal.repos' must be a table")]
+    for repo, policy in repos.items():
+        if policy not in HEAL_POLICIES:
+            problems.append(("error", f"{where(repo, 'heal.repos')}: invalid heal policy {policy!r} for {repo} (expected one of: {expected})"))
+    return problems
+
+
+def _is_positive_int(value: Any) -> bool:
+    return isinstance(value, int) and not isinstance(value, bool) and value > 0
+
+
+def _is_str_list(value: Any) -> bool:
+    return isinstance(value, list) and all(isinstance(v, str) for v in value)
+
+
+def _check_table_values(name: str, table: dict[str, Any], where: Any) -> list[tuple[str, str]]:
+    """Type and format errors for the values in feature table *name*."""
+    from . import retention
+
+    if name == "heal":
+        return _check_heal_policies(table, where)
+    problems: list[tuple[str, str]] = []
+
+    def bad(key: str, expected: str) -> None:
+        problems.append(("error", f"{where(key, name)}: '{name}.{key}' must be {expected}, got {table[key]!r}"))
+
+    for key, value in table.items():
+        if name == "sandbox" and key in ("timeout", "cpu_seconds", "max_output_bytes"):
+            if not _is_positive_int(value):
+                bad(key, "a positive integer")
+        elif name == "sandbox" and key == "env":
+            if not _is_str_list(value):
+                bad(key, "a list of variable names")
+        elif name == "commit" and key == "style":
+            if not isinstance(value, str):
+                bad(key, "a string")
+        elif name == "commit" and key == "recent":
+            if not _is_positive_int(value):
+                bad(key, "a positive integer")
+        elif name == "review" and key == "checklists":
+            if not _is_str_list(value):
+                bad(key, "a list of checklist names")
+                continue
+            for unknown in [v for v in value if v not in REVIEW_CHECKLISTS]:
+                expected = ", ".join(REVIEW_CHECKLISTS)
+                problems.append(("error", f"{where(key, name)}: unknown checklist {unknown!r} (expected one of: {expected})"))
+        elif name == "review" and key == "items":
+            if not _is_str_list(value):
+                bad(key, "a list of strings")
+        elif name == "retention" and key in retention.TARGETS:
+            try:
+                retention.parse_age(value if isinstance(value, str) else "")
+            except ValueError:
+                bad(key, "an age such as 30d, 12h, or 2w")
+    return problems
+
+
+def _check_file(
+    path: Path, keys: dict[str, str], tables: dict[str, frozenset[str]], *, project: bool
+) -> list[tuple[str, str]]:
+    """Problems in config file *path*, which may set *keys* and *tables*."""
+    problems: list[tuple[str, str]] = []
+    try:
+        text = path.read_text(encoding="utf-8")
+        data = tomllib.loads(text)
+    except (OSError, UnicodeDecodeError, tomllib.TOMLDecodeError) as exc:
+        return [("error", f"{path}: {exc}")]
+    lines = text.splitlines()
+
+    def where(key: str, table: str | None = None) -> str:
+        line = _key_line(lines, key, table)
+        return f"{path}:{line}" if line else str(path)
+
+    for key, value in data.items():
+        if key in keys:
+            if not isinstance(value, str):
+                problems.append(("error", f"{where(key)}: '{key}' must be a string"))
+        elif key in tables:
+            if not isinstance(value, dict):
+                problems.append(("error", f"{where(key)}: '{key}' must be a table ([{key}])"))
+                continue
+            for sub in value:
+                if sub not in tables[key]:
+                    allowed = ", ".join(sorted(tables[key]))
+                    problems.append(("error", f"{where(sub, key)}: unknown key '{key}.{sub}' (expected one of: {allowed})"))
+            known_subs = {sub: v for sub, v in value.items() if sub in tables[key]}
+            problems.extend(_check_table_values(key, known_subs, where))
+        elif project and key in _USER_SECRET_KEYS:
+            problems.append(("error", f"{where(key)}: '{key}' is ignored in {PROJECT_CONFIG_NAME}; set it in {user_config_path()} or the environment"))
+        else:
+            known = ", ".join(sorted([*keys, *tables]))
+            problems.append(("error", f"{where(key)}: unknown key '{key}' (expected one of: {known})"))
+    return problems
+
+
+def validate(current: Settings | None = None) -> list[tuple[str, str]]:
+    """Check settings and the config files; return ``(level, message)`` pairs.
+
+    ``level`` is ``error`` for things that will break a feature and ``warning``
+    for optional pieces that are missing.  Config-file messages carry
+    ``path:line`` context.
+    """
+    cfg = current or settings
+    problems: list[tuple[str, str]] = []
+    if not Path(cfg.workspace_root).is_dir():
+        problems.append(("error", f"OPENX_WORKSPACE_ROOT is not a directory: {cfg.workspace_root}"))
+    if not cfg.github_token:
+        problems.append(("error", "GITHUB_TOKEN is not set; GitHub tools will fail"))
+    if not cfg.anthropic_api_key:
+        problems.append(("warning", "ANTHROPIC_API_KEY is not set; AI review is skipped"))
+    if cfg.github_base_url and not re.match(r"^https?://", cfg.github_base_url):
+        problems.append(("error", f"GITHUB_BASE_URL must start with http:// or https://: {cfg.github_base_url}"))
+    if cfg.active_repo and not re.fullmatch(r"[\w.-]+/[\w.-]+", cfg.active_repo):
+        problems.append(("error", f"Active repo must be owner/repo: {cfg.active_repo}"))
+
+    user_path = user_config_path()
+    if user_path.is_file():
+        problems.extend(_check_file(user_path, {**_PROJECT_KEYS, **_USER_SECRET_KEYS}, USER_TABLES, project=False))
+
+    path = find_project_config(cfg.workspace_root)
+    if path is None:
+        return problems
+    from . import trust
+
+    if not trust.is_trusted(path.parent, cfg.data_dir):
+        problems.append(("warning", f"{path}: ignored until the workspace is trusted (openx trust {path.parent})"))
+    problems.extend(_check_file(path, _PROJECT_KEYS, PROJECT_TABLES, project=True))
+    return problems
+
+
+def heal_policy(repo: str) -> str:
+    """Heal policy for *repo*: ``[heal.repos]`` entry, then ``[heal] policy``, then full-auto."""
+    table = settings.project.get("heal") or {}
+    repos = table.get("repos") or {}
+    policy = (repos.get(repo) if isinstance(repos, dict) else None) or table.get("policy") or "full-auto"
+    if policy not in HEAL_POLICIES:
+        logger.warning("Unknown heal policy %r for %s; using analyze-only", policy, repo)
+        return "analyze-only"
+    return policy
+
+
 def resolve_repo(repo: str | None, *, required: bool = True) -> str:
     """Return a canonical owner/repo string."""
     resolved = (repo or "").strip() or (settings.active_repo or "").strip()
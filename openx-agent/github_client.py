@@ -6,18 +6,21 @@ import io
 import json
 import logging
 import re
+import string
 import threading
+import time
 import zipfile
 from concurrent.futures import ThreadPoolExecutor, as_completed
 from functools import lru_cache
-from typing import Any
+from typing import Any, Callable
 from urllib.parse import quote
 
 import httpx
 
-from .config import settings
+from .config import heal_policy, settings
 from . import cache as _cache
 from . import gh_cli
+from . import heal_history
 
 logger = logging.getLogger(__name__)
 _CHECK_CONCLUSION_FAILED = frozenset({
@@ -274,6 +277,45 @@ def comment_pr(repo_full_name: str, number: int, body: str) -> dict[str, Any]:
     comment = pr.create_issue_comment(body)
     return {"id": comment.id, "html_url": comment.html_url}
 
+def suggestion_body(replacement: str, body: str = "") -> str:
+    """Compose a review comment whose ```suggestion block replaces the selected lines."""
+    fence = "```"
+    while fence in replacement:  # keep nested fences in the replacement intact
+        fence += "`"
+    code = replacement.rstrip("\n")
+    # An empty suggestion deletes the selected lines.
+    block = f"{fence}suggestion\n{code}\n{fence}" if code else f"{fence}suggestion\n{fence}"
+    return f"{body.strip()}\n\n{block}" if body.strip() else block
+
+def suggest_change(
+    repo_full_name: str,
+    number: int,
+    path: str,
+    line: int,
+    replacement: str,
+    start_line: int | None = None,
+    body: str = "",
+    dry_run: bool = False,
+) -> dict[str, Any]:
+    """Post a review comment on lines start_line..line of *path* (new side of the
+    diff) that suggests *replacement*.  With *dry_run*, only return the body.
+    """
+    if line < 1:
+        raise ValueError("line must be at least 1")
+    if start_line is not None and not 1 <= start_line <= line:
+        raise ValueError(f"start_line must be between 1 and line ({line})")
+    text = suggestion_body(replacement, body)
+    if dry_run:
+        start = line if start_line is None else start_line
+        return {"status": "preview", "path": path, "start_line": start, "line": line, "body": text}
+    repo = get_repo(repo_full_name)
+    pr = repo.get_pull(number)
+    kwargs: dict[str, Any] = {"line": line, "side": "RIGHT"}
+    if start_line is not None and start_line != line:
+        kwargs.update(start_line=start_line, start_side="RIGHT")
+    comment = pr.create_review_comment(text, repo.get_commit(pr.head.sha), path, **kwargs)
+    return {"status": "posted", "id": comment.id, "html_url": comment.html_url}
+
 def merge_pr(repo_full_name: str, number: int, method: str = "merge") -> dict[str, Any]:
     repo = get_repo(repo_full_name)
     pr = repo.get_pull(number)
@@ -446,19 +488,180 @@ def close_issue(repo_full_name: str, number: int) -> dict[str, Any]:
     issue.edit(state="closed")
     return {"number": issue.number, "state": "closed"}
 
-def list_workflows(repo_full_name: str) -> list[dict[str, Any]]:
+def list_stale(
+    repo_full_name: str,
+    days: int = 60,
+    kind: str = "all",
+    exempt_labels: list[str] | None = None,
+) -> list[dict[str, Any]]:
+    """Open issues and/or PRs (kind: issues, prs, all) not updated for *days* days."""
+    if kind not in ("issues", "prs", "all"):
+        raise ValueError("kind must be issues, prs, or all")
+    exempt = {lb.lower() for lb in (exempt_labels or [])}
+    now = time.time()
+    cutoff = now - days * 86400
     repo = get_repo(repo_full_name)
-    workflows = repo.get_workflows()
-    return [
-        {
-            "id": wf.id,
-            "name": wf.name,
-            "path": wf.path,
-            "state": wf.state,
-            "html_url": wf.html_url,
-        }
-        for wf in workflows
-    ]
+    stale: list[dict[str, Any]] = []
+    # Oldest-updated first, so iteration can stop at the first fresh item.
+    for i in repo.get_issues(state="open", sort="updated", direction="asc"):
+        updated = i.updated_at.timestamp()
+        if updated > cutoff:
+            break
+        is_pr = i.pull_request is not None
+        if (kind == "issues" and is_pr) or (kind == "prs" and not is_pr):
+            continue
+        labels = [lb.name for lb in (i.labels or [])]
+        if exempt & {lb.lower() for lb in labels}:
+            continue
+        stale.append({
+            "number": i.number,
+            "kind": "pr" if is_pr else "issue",
+            "title": i.title,
+            "user": i.user.login if i.user else None,
+            "labels": labels,
+            "days_inactive": int((now - updated) // 86400),
+            "html_url": i.html_url,
+        })
+    return stale
+
+class _KeepUnknown(dict):
+    """format_map mapping that leaves unknown ``{name}`` fields as written."""
+
+    def __missing__(self, key: str) -> str:
+        return "{" + key + "}"
+
+def _check_sweep_template(comment: str) -> None:
+    try:
+        fields = [name for _, name, _, _ in string.Formatter().parse(comment) if name is not None]
+    except ValueError as e:
+        raise ValueError(f"Invalid comment template ({e}); write literal braces as {{{{ and }}}}") from None
+    for name in fields:
+        if not name.isidentifier():
+            raise ValueError(f"Unsupported field {{{name}}} in comment; use {{author}}, {{number}} or {{days}}")
+
+def sweep_stale(
+    repo_full_name: str,
+    numbers: list[int],
+    comment: str = "",
+    label: str = "",
+    close: bool = False,
+) -> dict[str, Any]:
+    """Comment on, label, and optionally close each issue/PR in *numbers*.
+
+    *comment* may use ``{author}``, ``{number}`` and ``{days}`` (days since the
+    last update); other ``{name}`` fields are left as written.  Failures are
+    reported per item; the sweep continues.
+    """
+    _check_sweep_template(comment)
+    repo = get_repo(repo_full_name)
+    now = time.time()
+    results: list[dict[str, Any]] = []
+    for number in numbers:
+        actions: list[str] = []
+        try:
+            issue = repo.get_issue(number)
+            if comment:
+                issue.create_comment(comment.format_map(_KeepUnknown(
+                    author=issue.user.login if issue.user else "",
+                    number=number,
+                    days=int((now - issue.updated_at.timestamp()) // 86400),
+                )))
+                actions.append("commented")
+            if label:
+                issue.add_to_labels(label)
+                actions.append("labeled")
+            if close:
+                issue.edit(state="closed")
+                actions.append("closed")
+            results.append({"number": number, "status": "ok", "actions": actions})
+        except Exception as e:
+            results.append({"number": number, "status": "error", "actions": actions, "message": str(e)})
+    failed = sum(1 for r in results if r["status"] == "error")
+    return {
+        "swept": len(results) - failed,
+        "failed": failed,
+        "results": results,
+    }
+
+def release_context(repo_full_name: str, since_tag: str = "", limit: int = 200) -> dict[str, Any]:
+    """Merged PRs on the default branch since *since_tag* (default: latest release/tag)."""
+    repo = get_repo(repo_full_name)
+    base = repo.default_branch
+    tag = since_tag.strip()
+    if not tag:
+        try:
+            tag = repo.get_latest_release().tag_name
+        except Exception:
+            tags = repo.get_tags()
+            tag = tags[0].name if tags.totalCount else ""
+    since = None
+    if tag:
+        since = repo.get_commit(tag).commit.committer.date
+
+    pulls: list[dict[str, Any]] = []
+    for pr in repo.get_pulls(state="closed", base=base, sort="updated", direction="desc"):
+        if since is not None and pr.updated_at < since:
+            break  # nothing updated before the tag can have been merged after it
+        if pr.merged_at is None or (since is not None and pr.merged_at <= since):
+            continue
+        pulls.append({
+            "number": pr.number,
+            "title": pr.title,
+            "user": pr.user.login if pr.user else None,
+            "labels": [lb.name for lb in (pr.labels or [])],
+            "merged_at": pr.merged_at.isoformat(),
+            "html_url": pr.html_url,
+        })
+        if len(pulls) >= limit:
+            break
+    pulls.sort(key=lambda p: p["merged_at"])
+    return {
+        "repo": repo_full_name,
+        "base": base,
+        "previous_tag": tag or None,
+        "since": since.isoformat() if since else None,
+        "pull_requests": pulls,
+    }
+
+def create_release(
+    repo_full_name: str,
+    tag: str,
+    name: str = "",
+    body: str = "",
+    target: str = "",
+    draft: bool = False,
+    prerelease: bool = False,
+) -> dict[str, Any]:
+    """Create a tag and GitHub release. *target* defaults to the default branch."""
+    repo = get_repo(repo_full_name)
+    kwargs: dict[str, Any] = {"draft": draft, "prerelease": prerelease}
+    if target:
+        kwargs["target_commitish"] = target
+    release = repo.create_git_release(tag, name or tag, body, **kwargs)
+    return {
+        "id": release.id,
+        "tag": release.tag_name,
+        "name": release.title,
+        "draft": release.draft,
+        "prerelease": release.prerelease,
+        "html_url": release.html_url,
+    }
+
+def list_workflows(repo_full_name: str) -> list[dict[str, Any]]:
+    def _fetch() -> list[dict[str, Any]]:
+        repo = get_repo(repo_full_name)
+        workflows = repo.get_workflows()
+        return [
+            {
+                "id": wf.id,
+                "name": wf.name,
+                "path": wf.path,
+                "state": wf.state,
+                "html_url": wf.html_url,
+            }
+            for wf in workflows
+        ]
+    return _cache.cached_list(f"list_workflows:{repo_full_name}", _cache.CACHE_TTL_WORKFLOWS, _fetch)
 
 def trigger_workflow(repo_full_name: str, workflow_id: int, ref: str, inputs: dict[str, Any] | None = None) -> dict[str, Any]:
     repo = get_repo(repo_full_name)
@@ -579,6 +782,11 @@ def get_failing_prs(repo_full_name: str) -> list[dict[str, Any]]:
     return results
 
 def get_ci_logs(repo_full_name: str, workflow_run_id: int) -> str:
+    return _cache.cached_logs(
+        repo_full_name, workflow_run_id, lambda: _fetch_ci_logs(repo_full_name, workflow_run_id)
+    )
+
+def _fetch_ci_logs(repo_full_name: str, workflow_run_id: int) -> str:
     resp = _api_request(
         "GET",
         f"/repos/{repo_full_name}/actions/runs/{workflow_run_id}/logs",
@@ -596,9 +804,78 @@ def get_ci_logs(repo_full_name: str, workflow_run_id: int) -> str:
             chunks.append(f"===== {name} =====\n{raw.strip()}\n")
     return "\n".join(chunks).strip()
 
-def analyze_ci_failure(logs: str) -> dict[str, str]:
+def get_ci_logs_page(
+    repo_full_name: str,
+    workflow_run_id: int,
+    offset: int = 0,
+    max_chars: int = 100_000,
+) -> dict[str, Any]:
+    """One page of CI logs.  Pass ``next_offset`` back as *offset* to continue;
+    it is ``None`` once the end of the logs is reached."""
+    logs = get_ci_logs(repo_full_name, workflow_run_id)
+    start = max(0, offset)
+    end = start + max(1, max_chars)
+    return {
+        "workflow_run_id": workflow_run_id,
+        "logs": logs[start:end],
+        "offset": start,
+        "next_offset": end if end < len(logs) else None,
+        "total_chars": len(logs),
+    }
+
+# error_type -> broad failure category reported alongside the fine-grained type.
+_FAILURE_CATEGORIES: dict[str, str] = {
+    "missing_dependency": "dependency",
+    "import_error": "dependency",
+    "syntax_error": "syntax",
+    "indentation_error": "syntax",
+    "name_error": "code",
+    "attribute_error": "code",
+    "test_assertion_failure": "test",
+    "test_failure": "test",
+    "build_failure": "build",
+    "npm_failure": "build",
+    "lint_failure": "lint",
+    "format_failure": "lint",
+}
+
+_FILE_REF_RE = re.compile(
+    r"([A-Za-z0-9_./-]+\.(?:py|js|jsx|ts|tsx|java|go|rb|php|cpp|c|cs|rs|yml|yaml|json))(?::(\d+))?"
+)
+
+def _suspect_files(logs: str, limit: int = 5) -> list[str]:
+    """Project files referenced in *logs*, most recent traceback frame first."""
+    refs = [(p, ln) for p, ln in reversed(re.findall(r'File "([^"]+)", line (\d+)', logs))]
+    refs += [(m.group(1), m.group(2)) for m in _FILE_REF_RE.finditer(logs)]
+    seen: dict[str, str] = {}
+    for path, line in refs:
+        if "site-packages" in path or "/lib/python" in path or path in seen:
+            continue
+        seen[path] = f"{path}:{line}" if line else path
+        if len(seen) >= limit:
+            break
+    return list(seen.values())
+
+def _classify(error_type: str, file_hint: str, reason: str, logs: str) -> dict[str, Any]:
+    """Structured analysis result: category, confidence, suspects, and next step."""
+    category = _FAILURE_CATEGORIES.get(error_type, "unknown")
+    if category == "unknown":
+        confidence = "low"
+    else:
+        confidence = "high" if file_hint else "medium"
+    return {
+        "error_type": error_type,
+        "category": category,
+        "confidence": confidence,
+        "file_hint": file_hint,
+        "reason": reason,
+        "suspect_files": _suspect_files(logs),
+        "next_step": "github_locate_code_context" if category != "unknown" or file_hint else None,
+    }
+
+def analyze_ci_failure(logs: str) -> dict[str, Any]:
     if not logs.strip():
-        return {"error_type": "unknown", "file_hint": "", "reason": "No logs provided"}
+        return _classify("unknown", "", "No logs provided", "")
 
     file_hint = ""
 
@@ -608,10 +885,7 @@ def analyze_ci_failure(logs: str) -> dict[str, str]:
         file_hint = f"{path}:{line}"
 
     if not file_hint:
-        file_match = re.search(
-            r"([A-Za-z0-9_./-]+\.(?:py|js|jsx|ts|tsx|java|go|rb|php|cpp|c|cs|rs|yml|yaml|json))(?::(\d+))?",
-            logs,
-        )
+        file_match = _FILE_REF_RE.search(logs)
         if file_match:
             file_hint = file_match.group(1)
             if file_match.group(2):
@@ -636,10 +910,10 @@ def analyze_ci_failure(logs: str) -> dict[str, str]:
         match = re.search(pat, logs, re.MULTILINE)
         if match:
             reason = match.group(0).strip()
-            return {"error_type": err_type, "file_hint": file_hint, "reason": reason}
+            return _classify(err_type, file_hint, reason, logs)
 
     tail = "\n".join(logs.strip().splitlines()[-10:])
-    return {"error_type": "unknown", "file_hint": file_hint, "reason": tail[:400]}
+    return _classify("unknown", file_hint, tail[:400], logs)
 
 def _decode_content(encoded: str) -> str:
     return base64.b64decode(encoded).decode("utf-8", errors="replace")
@@ -894,16 +1168,50 @@ def rerun_ci(repo_full_name: str, workflow_run_id: int) -> dict[str, Any]:
         "POST",
         f"/repos/{repo_full_name}/actions/runs/{workflow_run_id}/rerun",
     )
+    # The run id is reused by the new attempt, so its cached logs are stale.
+    _cache.invalidate_logs(repo_full_name, workflow_run_id)
     return {"status": "rerun_requested", "workflow_run_id": workflow_run_id}
 
-def heal_failing_pr(repo_full_name: str, pr_number: int | None = None) -> dict[str, Any]:
+# (step, total, message) callback for reporting heal pipeline progress.
+ProgressFn = Callable[[int, int, str], None]
+HEAL_STAGES = 7
+
+def heal_failing_pr(
+    repo_full_name: str,
+    pr_number: int | None = None,
+    progress: ProgressFn | None = None,
+) -> dict[str, Any]:
     """Run full CI healing pipeline: find failing PR -> get logs -> analyze ->
     locate context -> generate patch -> apply to PR -> rerun CI.
 
     If pr_number is None, heals the first failing PR in the repo.
+    The repo's heal policy (``[heal]`` in .openx.toml) may stop the pipeline
+    after analysis (analyze-only) or after patch generation (patch-only).
+    Every attempt that reaches a PR is recorded in the heal history.
+    *progress* is called as each of the HEAL_STAGES stages starts.
     """
+    started = time.monotonic()
+    policy = heal_policy(repo_full_name)
+    result = _run_heal_pipeline(repo_full_name, pr_number, policy, progress)
+    result["policy"] = policy
+    heal_history.record(repo_full_name, result, time.monotonic() - started)
+    return result
+
+def heal_history_summary(repo_full_name: str | None = None, recent: int = 20) -> dict[str, Any]:
+    """Resolve pending CI re-runs in the heal history, then summarize it."""
+    heal_history.resolve_pending(get_workflow_run)
+    return heal_history.summarize(repo_full_name, recent)
+
+def _run_heal_pipeline(
+    repo_full_name: str, pr_number: int | None, policy: str, progress: ProgressFn | None
+) -> dict[str, Any]:
+    def stage(step: int, message: str) -> None:
+        logger.info(message)
+        if progress is not None:
+            progress(step, HEAL_STAGES, message)
+
     try:
-        logger.info("Finding PRs with failing CI in %s", repo_full_name)
+        stage(1, f"Finding PRs with failing CI in {repo_full_name}")
         failing = get_failing_prs(repo_full_name)
     except Exception as e:
         return {"status": "error", "message": f"Failed to get failing PRs: {e}", "stage": "get_failing_prs"}
@@ -933,21 +1241,30 @@ def heal_failing_pr(repo_full_name: str, pr_number: int | None = None) -> dict[s
         }
 
     try:
-        logger.info("Fetching CI logs for run %d", run_id)
+        stage(2, f"Fetching CI logs for run {run_id}")
         logs = get_ci_logs(repo_full_name, run_id)
     except Exception as e:
         return {"status": "error", "pr_number": pr_num, "message": f"Failed to get CI logs: {e}", "stage": "get_ci_logs"}
 
-    logger.info("Analyzing CI failure")
+    stage(3, "Analyzing CI failure")
     error = analyze_ci_failure(logs)
+    if policy == "analyze-only":
+        return {
+            "status": "analyzed",
+            "pr_number": pr_num,
+            "workflow_run_id": run_id,
+            "error_type": error.get("error_type"),
+            "analysis": error,
+            "message": "Heal policy is analyze-only; no patch was generated.",
+        }
 
     try:
-        logger.info("Locating code context for %s", error.get("file_hint"))
+        stage(4, f"Locating code context for {error.get('file_hint')}")
         code_context = locate_code_context(repo_full_name, error)
     except Exception as e:
         return {"status": "error", "pr_number": pr_num, "message": f"Failed to locate code context: {e}", "stage": "locate_code_context"}
 
-    logger.info("Generating fix patch")
+    stage(5, "Generating fix patch")
     patch = generate_fix_patch(code_context, error)
     if not patch or not patch.strip():
         return {
@@ -957,22 +1274,34 @@ def heal_failing_pr(repo_full_name: str, pr_number: int | None = None) -> dict[s
             "reason": (error.get("reason") or "")[:300],
             "message": "No automated fix available for this error type. Consider manual fix or extend generate_fix_patch.",
         }
+    if policy == "patch-only":
+        return {
+            "status": "patch_ready",
+            "pr_number": pr_num,
+            "workflow_run_id": run_id,
+            "error_type": error.get("error_type"),
+            "patch": patch,
+            "message": "Heal policy is patch-only; review the patch and apply it with github_apply_fix_to_pr.",
+        }
 
     try:
-        logger.info("Applying patch to PR #%d", pr_num)
+        stage(6, f"Applying patch to PR #{pr_num}")
         result = apply_fix_to_pr(repo_full_name, pr_num, patch)
     except Exception as e:
         return {"status": "error", "pr_number": pr_num, "message": f"Failed to apply fix to PR: {e}", "stage": "apply_fix_to_pr"}
 
     try:
-        logger.info("Re-running CI for run %d", run_id)
+        stage(7, f"Re-running CI for run {run_id}")
         rerun_ci(repo_full_name, run_id)
     except Exception as e:
         result["rerun_error"] = str(e)
 
-    result["status"] = "healed"
+    result["status"] = "patched"
     result["pr_number"] = pr_num
     result["workflow_run_id"] = run_id
     result["error_type"] = error.get("error_type")
-    result["message"] = f"Applied fix for PR #{pr_num} ({error.get('error_type', 'unknown')}) and requested CI re-run."
+    result["message"] = (
+        f"Applied fix for PR #{pr_num} ({error.get('error_type', 'unknown')}) and requested CI re-run;"
+        " the heal history records whether it passes."
+    )
     return result
@@ -0,0 +1,175 @@
+"""Persistent record of CI self-heal attempts.
+
+Each call to ``heal_failing_pr`` that targets a PR appends one JSON line to
+``heal_history.jsonl`` under the data directory, so maintainers can see
+whether auto-healing is paying off per repository over time.
+
+A patched PR is only counted as healed once the re-run CI passes: such
+attempts are recorded with ``ci = "pending"`` and resolved by
+:func:`resolve_pending` when the history is read.
+"""
+
+from __future__ import annotations
+
+import json
+import logging
+import threading
+import time
+from collections import Counter, defaultdict
+from datetime import datetime
+from pathlib import Path
+from typing import Any, Callable
+
+from .config import settings
+
+logger = logging.getLogger(__name__)
+_HISTORY_FILE = "heal_history.jsonl"
+_lock = threading.Lock()
+# Outcomes of runs that the heal policy ended before a fix could be applied.
+_POLICY_STOPPED = ("analyzed", "patch_ready")
+
+def _path() -> Path:
+    return Path(settings.data_dir).expanduser() / _HISTORY_FILE
+
+def record(repo_full_name: str, result: dict[str, Any], duration: float) -> None:
+    """Append one heal attempt.  Results that never reached a PR are skipped."""
+    if result.get("pr_number") is None:
+        return
+    entry = {
+        "timestamp": time.time(),
+        "repo": repo_full_name,
+        "pr_number": result.get("pr_number"),
+        "error_type": result.get("error_type"),
+        "status": result.get("status"),
+        "stage": result.get("stage"),
+        "files": [c.get("path") for c in result.get("commits", [])],
+        "duration_s": round(duration, 2),
+        "workflow_run_id": result.get("workflow_run_id"),
+        "ci": "pending" if result.get("status") == "patched" and not result.get("rerun_error") else None,
+    }
+    path = _path()
+    try:
+        with _lock:
+            path.parent.mkdir(parents=True, exist_ok=True)
+            with open(path, "a", encoding="utf-8") as fh:
+                fh.write(json.dumps(entry) + "\n")
+    except OSError as exc:
+        logger.warning("Could not record heal attempt: %s", exc)
+
+def load(repo_full_name: str | None = None) -> list[dict[str, Any]]:
+    """Return recorded attempts (oldest first), optionally filtered by repo."""
+    path = _path()
+    if not path.is_file():
+        return []
+    entries: list[dict[str, Any]] = []
+    with _lock, open(path, encoding="utf-8") as fh:
+        for line in fh:
+            try:
+                entry = json.loads(line)
+            except json.JSONDecodeError:
+                continue
+            if repo_full_name and entry.get("repo") != repo_full_name:
+                continue
+            entries.append(entry)
+    return entries
+
+def _key(entry: dict[str, Any]) -> tuple[Any, ...]:
+    return entry.get("timestamp"), entry.get("repo"), entry.get("pr_number")
+
+def resolve_pending(lookup: Callable[[str, int], dict[str, Any] | None]) -> int:
+    """Fill in the CI conclusion of attempts whose re-run was still pending.
+
+    *lookup(repo, run_id)* returns the workflow run (``status``, ``conclusion``,
+    ``updated_at``).  A run only counts once it completed after the heal
+    started, so the original failure is never mistaken for the re-run.
+    Returns the number of entries resolved.
+    """
+    conclusions: dict[tuple[Any, ...], str] = {}
+    for entry in load():
+        if entry.get("ci") != "pending" or not entry.get("workflow_run_id"):
+            continue
+        try:
+            run = lookup(entry["repo"], int(entry["workflow_run_id"]))
+        except Exception as exc:  # network or auth errors leave the entry pending
+            logger.warning("Could not check run %s: %s", entry["workflow_run_id"], exc)
+            continue
+        started = entry.get("timestamp", 0) - (entry.get("duration_s") or 0)
+        if not run or run.get("status") != "completed" or not run.get("updated_at"):
+            continue
+        if datetime.fromisoformat(run["updated_at"]).timestamp() >= started:
+            conclusions[_key(entry)] = run.get("conclusion") or "unknown"
+    if not conclusions:
+        return 0
+
+    path = _path()
+    with _lock:
+        out: list[str] = []
+        for line in path.read_text(encoding="utf-8").splitlines():
+            try:
+                entry = json.loads(line)
+            except json.JSONDecodeError:
+                continue
+            if entry.get("ci") == "pending" and _key(entry) in conclusions:
+                entry["ci"] = conclusions[_key(entry)]
+            out.append(json.dumps(entry))
+        path.write_text("\n".join(out) + "\n", encoding="utf-8")
+    return len(conclusions)
+
+def prune(cutoff: float | None = None) -> int:
+    """Drop attempts recorded before *cutoff* (epoch seconds; None drops all).
+
+    Returns the number of entries removed.
+    """
+    path = _path()
+    if not path.is_file():
+        return 0
+    with _lock:
+        lines = path.read_text(encoding="utf-8").splitlines()
+        kept: list[str] = []
+        for line in lines:
+            try:
+                timestamp = json.loads(line).get("timestamp", 0)
+            except json.JSONDecodeError:
+                continue
+            if cutoff is not None and timestamp >= cutoff:
+                kept.append(line)
+        if kept:
+            path.write_text("\n".join(kept) + "\n", encoding="utf-8")
+        else:
+            path.unlink()
+    return len(lines) - len(kept)
+
+def summarize(repo_full_name: str | None = None, recent: int = 20) -> dict[str, Any]:
+    """Per-repo success rate, outcome counts, and the most recent attempts.
+
+    ``success_rate`` counts only attempts whose re-run CI passed, out of those
+    the heal policy allowed to apply a fix; runs an analyze-only or patch-only
+    policy stopped early are reported as ``policy_stopped`` instead.
+    ``patches_applied`` counts every attempt that pushed a fix.
+    """
+    entries = load(repo_full_name)
+    by_repo: dict[str, list[dict[str, Any]]] = defaultdict(list)
+    for entry in entries:
+        by_repo[entry.get("repo", "")].append(entry)
+
+    repos: dict[str, Any] = {}
+    for repo, items in sorted(by_repo.items()):
+        # "healed" is how attempts were recorded before CI outcomes were tracked.
+        patched = sum(1 for e in items if e.get("status") in ("patched", "healed"))
+        healed = sum(1 for e in items if e.get("ci") == "success")
+        stopped = sum(1 for e in items if e.get("status") in _POLICY_STOPPED)
+        eligible = len(items) - stopped
+        durations = [e.get("duration_s") or 0 for e in items]
+        repos[repo] = {
+            "attempts": len(items),
+            "policy_stopped": stopped,
+            "patches_applied": patched,
+            "healed": healed,
+            "ci_pending": sum(1 for e in items if e.get("ci") == "pending"),
+            "success_rate": round(healed / eligible, 3) if eligible else None,
+            "outcomes": dict(Counter(e.get("status") for e in items)),
+            "error_types": dict(Counter(e.get("error_type") for e in items if e.get("error_type"))),
+            "avg_duration_s": round(sum(durations) / len(durations), 2),
+            "last_attempt": max(e.get("timestamp", 0) for e in items),
+        }
+    return {"repos": repos, "recent": entries[-recent:][::-1] if recent > 0 else []}
@@ -0,0 +1,68 @@
+"""Size guard for tool responses.
+
+A tool result larger than ``OPENX_MAX_OUTPUT_BYTES`` is not returned inline:
+the full payload is written to a file under the cache directory and the
+client receives a preview plus an explicit truncation marker naming that
+file, so one huge response cannot exhaust the client's memory or context.
+"""
+
+from __future__ import annotations
+
+import functools
+import json
+import logging
+import re
+import time
+from typing import Any, Callable
+
+from . import paths
+from .config import settings
+
+logger = logging.getLogger(__name__)
+
+def _spill(name: str, payload: str, suffix: str) -> str | None:
+    directory = paths.cache_dir() / "outputs"
+    safe = re.sub(r"[^\w.-]", "_", name)
+    target = directory / f"{safe}-{time.time_ns()}{suffix}"
+    try:
+        directory.mkdir(parents=True, exist_ok=True)
+        target.write_text(payload, encoding="utf-8")
+    except OSError as exc:
+        logger.warning("Could not save oversized %s output: %s", name, exc)
+        return None
+    return str(target)
+
+def limit(name: str, result: Any) -> Any:
+    """Return *result*, or a truncated stand-in if it exceeds the size limit."""
+    max_bytes = settings.max_output_bytes
+    if max_bytes <= 0 or result is None:
+        return result
+    is_text = isinstance(result, str)
+    payload = result if is_text else json.dumps(result, indent=2, default=str)
+    data = payload.encode("utf-8")
+    if len(data) <= max_bytes:
+        return result
+
+    preview = data[:max_bytes].decode("utf-8", errors="ignore")
+    omitted_kb = (len(data) - max_bytes + 1023) // 1024
+    path = _spill(name, payload, ".txt" if is_text else ".json")
+    where = f"full output saved to {path}" if path else "full output discarded"
+    marker = f"[... truncated {omitted_kb} KB; {where} ...]"
+    if is_text:
+        return f"{preview}\n{marker}"
+    return {
+        "status": "truncated",
+        "message": marker,
+        "total_bytes": len(data),
+        "full_output_path": path,
+        "preview": preview,
+    }
+
+def guard(name: str, fn: Callable[..., Any]) -> Callable[..., Any]:
+    """Wrap tool *fn* so its result passes through :func:`limit`."""
+
+    @functools.wraps(fn)
+    def wrapper(*args: Any, **kwargs: Any) -> Any:
+        return limit(name, fn(*args, **kwargs))
+
+    return wrapper
@@ -0,0 +1,30 @@
+"""XDG base-directory paths for OpenX's persisted files.
+
+``$XDG_*_HOME`` variables are honoured when set; otherwise the XDG defaults
+under ``~`` are used.
+"""
+
+from __future__ import annotations
+
+import os
+from pathlib import Path
+
+APP_NAME = "openx"
+
+def _xdg(var: str, default: str) -> Path:
+    value = os.environ.get(var, "").strip()
+    base = Path(value) if value and os.path.isabs(value) else Path.home() / default
+    return base / APP_NAME
+
+def config_dir() -> Path:
+    return _xdg("XDG_CONFIG_HOME", ".config")
+
+def data_dir() -> Path:
+    return _xdg("XDG_DATA_HOME", ".local/share")
+
+def cache_dir() -> Path:
+    return _xdg("XDG_CACHE_HOME", ".cache")
+
+def state_dir() -> Path:
+    """Logs and other non-essential runtime state."""
+    return _xdg("XDG_STATE_HOME", ".local/state")
@@ -0,0 +1,76 @@
+"""Purging of locally persisted data.
+
+``openx purge`` removes the heal history and saved oversized tool outputs
+on demand; a ``[retention]`` table in the user config file applies the same
+purge automatically at startup::
+
+    [retention]
+    history = "90d"
+    cache = "7d"
+"""
+
+from __future__ import annotations
+
+import logging
+import re
+import time
+
+from . import heal_history, paths
+from .config import settings
+
+logger = logging.getLogger(__name__)
+TARGETS = ("history", "cache")
+_UNITS = {"s": 1, "m": 60, "h": 3600, "d": 86400, "w": 7 * 86400}
+
+def parse_age(text: str) -> float:
+    """Seconds in an age such as ``30d``, ``12h``, or ``2w``."""
+    match = re.fullmatch(r"\s*(\d+)\s*([smhdw])\s*", text or "")
+    if not match:
+        raise ValueError(f"Invalid age {text!r}; use e.g. 30d, 12h, or 2w")
+    return int(match.group(1)) * _UNITS[match.group(2)]
+
+def _purge_cache(cutoff: float | None) -> int:
+    directory = paths.cache_dir() / "outputs"
+    if not directory.is_dir():
+        return 0
+    removed = 0
+    for path in directory.iterdir():
+        try:
+            if path.is_file() and (cutoff is None or path.stat().st_mtime < cutoff):
+                path.unlink()
+                removed += 1
+        except OSError as exc:
+            logger.warning("Could not remove %s: %s", path, exc)
+    return removed
+
+def purge(target: str, older_than: str | None = None) -> dict[str, int]:
+    """Remove data for *target* (history, cache, or all), optionally only entries older than *older_than*."""
+    if target != "all" and target not in TARGETS:
+        raise ValueError(f"Unknown purge target {target!r}; expected one of: {', '.join((*TARGETS, 'all'))}")
+    cutoff = time.time() - parse_age(older_than) if older_than else None
+    removed: dict[str, int] = {}
+    if target in ("history", "all"):
+        removed["history"] = heal_history.prune(cutoff)
+    if target in ("cache", "all"):
+        removed["cache"] = _purge_cache(cutoff)
+    return removed
+
+def apply_policy() -> dict[str, int]:
+    """Purge according to ``[retention]`` in the user config; no-op when unset.
+
+    Retention governs data shared by every workspace, so a project's
+    .openx.toml cannot set it.
+    """
+    table = settings.user.get("retention") or {}
+    removed: dict[str, int] = {}
+    for target in TARGETS:
+        age = table.get(target)
+        if not age:
+            continue
+        try:
+            removed.update(purge(target, str(age)))
+        except ValueError as exc:
+            logger.warning("Ignoring retention.%s: %s", target, exc)
+    if any(removed.values()):
+        logger.info("Retention purge removed %s", removed)
+    return removed
@@ -0,0 +1,123 @@
+"""Resource-limited execution of local commands.
+
+Commands run with a wall-clock timeout, a CPU-time limit (POSIX only), an
+environment reduced to an allowlist, and captured output capped to its last
+``max_output_bytes`` — a runaway is killed with its whole process group
+rather than left to hang the server or flood a tool response.
+
+Limits come from the ``[sandbox]`` table of ``.openx.toml``::
+
+    [sandbox]
+    timeout = 600             # wall-clock seconds
+    cpu_seconds = 600         # RLIMIT_CPU
+    max_output_bytes = 65536
+    env = ["CARGO_HOME"]      # variables passed through besides the defaults
+"""
+
+from __future__ import annotations
+
+import logging
+import os
+import signal
+import subprocess
+import threading
+from pathlib import Path
+from typing import Any
+
+from .config import settings
+
+logger = logging.getLogger(__name__)
+DEFAULT_TIMEOUT = 600
+DEFAULT_CPU_SECONDS = 600
+DEFAULT_MAX_OUTPUT = 64 * 1024
+ENV_ALLOWLIST = (
+    "PATH", "HOME", "USER", "LANG", "LC_ALL", "LC_CTYPE", "TERM", "TMPDIR",
+    "SHELL", "VIRTUAL_ENV", "PYTHONPATH", "GOPATH", "CARGO_HOME", "RUSTUP_HOME",
+    "JAVA_HOME", "NODE_PATH",
+)
+
+def _positive_int(table: dict[str, Any], key: str, default: int) -> int:
+    value = table.get(key, default)
+    if isinstance(value, bool) or not isinstance(value, int) or value <= 0:
+        logger.warning("Ignoring sandbox.%s = %r: expected a positive integer", key, value)
+        return default
+    return value
+
+def limits() -> dict[str, Any]:
+    """Effective limits: project ``[sandbox]`` values over the defaults.
+
+    Invalid values fall back to the default (``openx config check`` reports them).
+    """
+    table = settings.project.get("sandbox") or {}
+    extra = table.get("env", [])
+    if not isinstance(extra, list):
+        logger.warning("Ignoring sandbox.env = %r: expected a list of names", extra)
+        extra = []
+    return {
+        "timeout": _positive_int(table, "timeout", DEFAULT_TIMEOUT),
+        "cpu_seconds": _positive_int(table, "cpu_seconds", DEFAULT_CPU_SECONDS),
+        "max_output_bytes": _positive_int(table, "max_output_bytes", DEFAULT_MAX_OUTPUT),
+        "env": [*ENV_ALLOWLIST, *(str(v) for v in extra)],
+    }
+
+def _env(allow: list[str]) -> dict[str, str]:
+    return {k: v for k, v in os.environ.items() if k in allow}
+
+def run(argv: list[str], cwd: Path) -> dict[str, Any]:
+    """Run *argv* in *cwd* under the configured limits.
+
+    Returns exit_code (None if killed), output (stdout+stderr, tail only),
+    truncated_bytes, and timed_out.  Raises OSError if the command cannot start.
+    """
+    lim = limits()
+    max_out = lim["max_output_bytes"]
+    if os.name == "posix":
+        # Tools run on worker threads, where preexec_fn is unsafe; let the
+        # shell apply RLIMIT_CPU and then exec the command in place.
+        argv = ["/bin/sh", "-c", 'ulimit -t "$0" && exec "$@"', str(lim["cpu_seconds"]), *argv]
+
+    proc = subprocess.Popen(
+        argv,
+        cwd=cwd,
+        stdin=subprocess.DEVNULL,
+        stdout=subprocess.PIPE,
+        stderr=subprocess.STDOUT,
+        env=_env(lim["env"]),
+        start_new_session=True,
+    )
+    buf = bytearray()
+    total = 0
+
+    def _drain() -> None:
+        nonlocal total
+        assert proc.stdout is not None
+        for chunk in iter(lambda: proc.stdout.read(65536), b""):
+            total += len(chunk)
+            buf.extend(chunk)
+            if len(buf) > max_out:
+                del buf[: len(buf) - max_out]
+
+    reader = threading.Thread(target=_drain, daemon=True)
+    reader.start()
+    timed_out = False
+    try:
+        proc.wait(timeout=lim["timeout"])
+    except subprocess.TimeoutExpired:
+        timed_out = True
+        try:
+            os.killpg(proc.pid, signal.SIGKILL)
+        except (OSError, AttributeError):
+            proc.kill()
+        proc.wait()
+    reader.join(timeout=5)
+
+    truncated = max(0, total - len(buf))
+    output = buf.decode("utf-8", errors="replace")
+    if truncated:
+        output = f"[... {truncated} bytes truncated ...]\n{output}"
+    return {
+        "exit_code": None if timed_out else proc.returncode,
+        "output": output.strip(),
+        "truncated_bytes": truncated,
+        "timed_out": timed_out,
+    }
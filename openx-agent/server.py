@@ -8,17 +8,21 @@ and use the full OpenX toolset.
 
 from __future__ import annotations
 
+import importlib.metadata
 import json
 import logging
 import os
+import re
 import sys
 from contextlib import asynccontextmanager
+from pathlib import Path
 from typing import Any
 
 from mcp.server.fastmcp import FastMCP
 from starlette.requests import Request
 from starlette.responses import JSONResponse
 
+from . import output_guard, tool_stats
 from .tools.analysis import register as register_analysis_tools
 from .tools.github import register as register_github_tools
 from .tools.workspace_tools import register as register_workspace_tools
@@ -38,7 +42,19 @@ async def lifespan(server: FastMCP):
     except Exception:
         pass
 
-mcp = FastMCP(
+class OpenXMCP(FastMCP):
+    """FastMCP whose tools run on worker threads, are tracked by tool_stats,
+    and have oversized results truncated by output_guard."""
+
+    def add_tool(self, fn: Any, name: str | None = None, *args: Any, **kwargs: Any) -> Any:
+        tool_name = name or fn.__name__
+        if kwargs.get("annotations") is None:
+            # Help pages and clients rely on readOnlyHint/destructiveHint.
+            raise TypeError(f"Tool {tool_name} must be registered with annotations")
+        wrapped = tool_stats.instrument(tool_name, output_guard.guard(tool_name, fn))
+        return super().add_tool(wrapped, name, *args, **kwargs)
+
+mcp = OpenXMCP(
     name="OpenX",
     instructions=(
         "OpenX is an AI-powered GitHub automation server. "
@@ -58,14 +74,18 @@ register_analysis_tools(mcp)
 @mcp.resource("openx://config")
 def server_config() -> str:
     """Current OpenX server configuration (secrets redacted)."""
-    from .config import settings
+    from .config import heal_policy, settings
 
     return json.dumps(
         {
             "github_base_url": settings.github_base_url or "https://api.github.com",
             "anthropic_model": settings.anthropic_model,
             "workspace_root": settings.workspace_root,
+            "data_dir": settings.data_dir,
             "active_repo": settings.active_repo,
+            "user_config": settings.user_config_path,
+            "project_config": settings.project_config_path,
+            "heal_policy": heal_policy(settings.active_repo or ""),
             "github_token_configured": bool(settings.github_token),
             "anthropic_key_configured": bool(settings.anthropic_api_key),
         },
@@ -77,6 +97,27 @@ def server_help() -> str:
     """OpenX usage guide and full tool reference."""
     return _HELP_TEXT
 
+@mcp.resource("openx://motd")
+def server_motd() -> str:
+    """Operator-configured message of the day (empty when unset)."""
+    from .config import settings
+
+    return settings.motd or ""
+
+@mcp.resource("openx://stats")
+def server_tool_stats() -> str:
+    """Per-tool latency and error rates since startup, slowest first."""
+    return json.dumps(tool_stats.tool_stats(), indent=2)
+
+@mcp.resource("openx://help/{tool}")
+async def tool_help(tool: str) -> str:
+    """Help page for a single tool: synopsis, arguments, example, and side effects."""
+    tools = {t.name: t for t in await mcp.list_tools()}
+    info = tools.get(tool)
+    if info is None:
+        return f"Unknown tool: {tool}. Read openx://help for the full list."
+    return _format_tool_help(info, tools)
+
 @mcp.resource("github://{owner}/{repo}/readme")
 def repo_readme(owner: str, repo: str) -> str:
     """README content for a GitHub repository."""
@@ -134,10 +175,165 @@ def github_workflow(task: str) -> str:
         "For local changes: read/write files, then git add/commit/push."
     )
 
+_REVIEW_CHECKLISTS: dict[str, list[str]] = {
+    "security": [
+        "No secrets, tokens, or credentials added",
+        "User input is validated before use in queries, paths, or shell commands",
+    ],
+    "tests": [
+        "New behaviour is covered by tests",
+        "Existing tests still pass in CI",
+    ],
+    "docs": [
+        "README / docs updated for user-facing changes",
+        "Public functions have accurate docstrings",
+    ],
+    "perf": [
+        "No new N+1 API calls or unbounded loops over remote data",
+        "Large inputs are paged or truncated",
+    ],
+}
+
+def _review_checklist() -> list[str]:
+    """Checklist items from ``[review]`` in .openx.toml (default: every built-in list)."""
+    from .config import settings
+
+    table = settings.project.get("review") or {}
+    names = table.get("checklists") or list(_REVIEW_CHECKLISTS)
+    items = [item for name in names for item in _REVIEW_CHECKLISTS.get(name, [])]
+    return items + [str(item) for item in table.get("items", [])]
+
+@mcp.prompt()
+def review_pr(repo: str, pr_number: int) -> str:
+    """Review a PR against the project's checklist and post the result."""
+    checklist = "\n".join(f"- [ ] {item}" for item in _review_checklist())
+    return (
+        f"Review PR #{pr_number} in `{repo}`. Fetch it with github_get_pr and read "
+        "the diff and CI checks.\n\n"
+        f"Evaluate every item of this checklist:\n{checklist}\n\n"
+        "Write the review: a short summary, concrete issues with file/line "
+        "references, then the checklist with each item marked [x] (satisfied) "
+        "or left [ ] with a one-line reason. Show it to the user and post it "
+        "with github_comment_pr only once they approve."
+    )
+
+@mcp.prompt()
+def release(repo: str, version: str) -> str:
+    """Guided release: gather merged PRs, draft notes, confirm, then publish."""
+    return (
+        f"Prepare release `{version}` of `{repo}`.\n\n"
+        "1. Call github_release_context to list PRs merged since the previous tag.\n"
+        "2. Draft release notes in Markdown, grouped by label (features, fixes, "
+        "docs, other) with PR numbers and authors.\n"
+        "3. Show the draft and wait for the user to approve or edit it.\n"
+        f"4. Only after approval, call github_create_release with tag `{version}` "
+        "and the approved notes, then share the release URL."
+    )
+
 @mcp.custom_route("/health", methods=["GET"])
 async def health_check(request: Request) -> JSONResponse:
     return JSONResponse({"status": "ok"})
 
+@mcp.custom_route("/status", methods=["GET"])
+async def status(request: Request) -> JSONResponse:
+    """Lightweight heartbeat: queued/running tool calls and worker capacity.
+
+    ``state`` is ``busy`` when calls are waiting for a free worker thread.
+    """
+    return JSONResponse(tool_stats.status())
+
+def _server_version() -> str:
+    try:
+        return importlib.metadata.version("openx-mcp")
+    except importlib.metadata.PackageNotFoundError:
+        return "unknown"
+
+@mcp.custom_route("/capabilities", methods=["GET"])
+async def capabilities(request: Request) -> JSONResponse:
+    """Advertise version, tools, resources, and optional features for client negotiation."""
+    from .config import settings
+
+    tools = await mcp.list_tools()
+    templates = await mcp.list_resource_templates()
+    resources = await mcp.list_resources()
+    return JSONResponse({
+        "name": mcp.name,
+        "version": _server_version(),
+        "motd": settings.motd,
+        "protocol": "mcp",
+        "tools": sorted(t.name for t in tools),
+        "resources": sorted(
+            [str(r.uri) for r in resources] + [t.uriTemplate for t in templates]
+        ),
+        "features": {
+            "streaming": False,
+            "progress": True,
+            "heal_history": True,
+            "project_config": True,
+        },
+    })
+
+_EXAMPLE_VALUES: dict[str, Any] = {
+    "string": "...", "integer": 1, "number": 1, "boolean": True, "array": [], "object": {},
+}
+
+def _schema_type(prop: dict[str, Any]) -> str:
+    """Best-effort type name for a JSON-schema property (handles Optional unions)."""
+    if "type" in prop:
+        return str(prop["type"])
+    types = [p.get("type") for p in prop.get("anyOf", []) if p.get("type") not in (None, "null")]
+    return str(types[0]) if types else "any"
+
+def _format_tool_help(info: Any, tools: dict[str, Any]) -> str:
+    """Render one tool's help page from its MCP input schema."""
+    schema = info.inputSchema or {}
+    props: dict[str, Any] = schema.get("properties", {})
+    required = set(schema.get("required", []))
+
+    sig_parts: list[str] = []
+    arg_lines: list[str] = []
+    example: dict[str, Any] = {}
+    for name, prop in props.items():
+        typ = _schema_type(prop)
+        if name in required:
+            sig_parts.append(f"{name}: {typ}")
+            arg_lines.append(f"  {name:<20} {typ:<8} required")
+            example[name] = _EXAMPLE_VALUES.get(typ, "...")
+        else:
+            default = json.dumps(prop.get("default"))
+            sig_parts.append(f"{name}: {typ} = {default}")
+            arg_lines.append(f"  {name:<20} {typ:<8} optional (default {default})")
+
+    # Tools this one's description names, plus tools whose descriptions name it.
+    def names(text: str | None, name: str) -> bool:
+        return re.search(rf"\b{re.escape(name)}\b", text or "") is not None
+
+    related = sorted(
+        n for n, other in tools.items()
+        if n != info.name and (names(info.description, n) or names(other.description, info.name))
+    )
+    annotations = info.annotations
+    mutates = not (annotations and annotations.readOnlyHint)
+    destructive = mutates and (annotations is None or annotations.destructiveHint is not False)
+
+    return "\n".join([
+        info.name,
+        "",
+        "Synopsis:",
+        f"  {info.name}({', '.join(sig_parts)})",
+        "",
+        (info.description or "").strip(),
+        "",
+        "Arguments:",
+        *(arg_lines or ["  (none)"]),
+        "",
+        "Example:",
+        f"  {info.name} {json.dumps(example)}",
+        "",
+        f"Mutates state: {'yes (destructive)' if destructive else 'yes' if mutates else 'no'}",
+        f"Related: {', '.join(related) or '(none)'}",
+    ])
+
 _HELP_TEXT = """\
 OpenX — AI-Powered GitHub Automation MCP Server
 
@@ -147,6 +343,7 @@ GitHub Tools:
   github_get_pr               Get PR details with diff and CI status
   github_create_pr            Create a new pull request
   github_comment_pr           Comment on a PR
+  github_suggest_change       Suggest a replacement on PR lines
   github_merge_pr             Merge a PR (merge/squash/rebase)
   github_get_readme           Get README content
   github_update_readme        Create or update README
@@ -155,11 +352,16 @@ GitHub Tools:
   github_create_issue         Create a new issue
   github_comment_issue        Comment on an issue
   github_close_issue          Close an issue
+  github_list_stale           List inactive issues/PRs
+  github_sweep_stale          Comment/label/close stale issues/PRs
+  github_release_context      Merged PRs since the last tag
+  github_create_release       Create a tag and release
   github_list_workflows       List GitHub Actions workflows
   github_trigger_workflow     Trigger a workflow dispatch
   github_list_workflow_runs   List workflow runs
   github_get_workflow_run     Get workflow run details
   github_run_gh_command       Run a raw gh CLI command
+  github_refresh_cache        Drop cached GitHub responses
 
 CI/CD Self-Healing:
   github_get_failing_prs      List PRs with failed CI
@@ -170,15 +372,23 @@ CI/CD Self-Healing:
   github_apply_fix_to_pr      Apply patch to PR branch
   github_rerun_ci             Re-run a CI workflow
   github_heal_failing_pr      Auto-heal a failing PR (end-to-end)
+  github_heal_history         Heal success rate and recent attempts
 
 Workspace:
   workspace_read_file         Read a file from the workspace
   workspace_write_file        Write content to a file
   workspace_list_dir          List files and directories
+  workspace_grep              Regex search across workspace files
+  workspace_find_symbol       Locate function/class/type definitions
   workspace_git_status        Show git status
   workspace_git_add           Stage files
+  workspace_commit_context    Staged diff + recent commit style
   workspace_git_commit        Commit staged changes
   workspace_git_push          Push to remote
+  workspace_git_stash         Stash uncommitted changes
+  workspace_git_stash_list    List stashes
+  workspace_git_stash_pop     Apply and drop a stash
+  workspace_apply_patch_safely  Apply a patch in a worktree, test, then fast-forward
 
 Analysis:
   analysis_analyze_repo       Run full code analysis
@@ -186,18 +396,119 @@ Analysis:
 Resources:
   openx://config              Server configuration
   openx://help                This help text
+  openx://motd                Message of the day
+  openx://stats               Per-tool latency and error rates
+  openx://help/{tool}         Help page for a single tool
   github://{owner}/{repo}/readme    README content
   github://{owner}/{repo}/prs      Open pull requests
   github://{owner}/{repo}/issues/{state}  Issues (open/closed/all)
 """
 
+def _print_paths() -> None:
+    """``openx paths``: show where OpenX reads and writes its files."""
+    from . import paths
+    from .config import settings
+
+    rows = [
+        ("config", paths.config_dir()),
+        ("data", settings.data_dir),
+        ("cache", paths.cache_dir()),
+        ("state", paths.state_dir()),
+        ("user", settings.user_config_path or f"{paths.config_dir() / 'config.toml'} (not found)"),
+        ("project", settings.project_config_path or "(none)"),
+    ]
+    for label, value in rows:
+        print(f"{label:<8} {value}")
+
+def _trust_command(args: list[str]) -> int:
+    """``openx trust [DIR]``, ``openx trust --list``, ``openx untrust DIR``."""
+    from . import trust
+    from .config import settings
+
+    if args[:1] == ["untrust"]:
+        if len(args) < 2:
+            print("usage: openx untrust DIR", file=sys.stderr)
+            return 2
+        if not trust.untrust(args[1]):
+            print(f"Not trusted: {args[1]}", file=sys.stderr)
+            return 1
+        print(f"Untrusted {args[1]}")
+        return 0
+    if args[1:2] == ["--list"]:
+        for entry in trust.trusted():
+            print(entry)
+        return 0
+    target = args[1] if len(args) > 1 else settings.workspace_root
+    if not Path(target).expanduser().is_dir():
+        print(f"Not a directory: {target}", file=sys.stderr)
+        return 1
+    print(f"Trusted {trust.trust(target)}")
+    return 0
+
+def _purge_command(args: list[str]) -> int:
+    """``openx purge {history|cache|all} [--older-than AGE] [--yes]``."""
+    from . import retention
+
+    target, older_than, confirmed = None, None, False
+    rest = args[1:]
+    while rest:
+        arg = rest.pop(0)
+        if arg == "--older-than" and rest:
+            older_than = rest.pop(0)
+        elif arg.startswith("--older-than="):
+            older_than = arg.split("=", 1)[1]
+        elif arg in ("--yes", "-y"):
+            confirmed = True
+        else:
+            target = arg
+    if target is None:
+        print("usage: openx purge {history|cache|all} [--older-than AGE] [--yes]", file=sys.stderr)
+        return 2
+    if target == "all" and not confirmed:
+        print("openx purge all deletes the heal history and saved outputs; pass --yes to confirm", file=sys.stderr)
+        return 2
+    try:
+        removed = retention.purge(target, older_than)
+    except ValueError as exc:
+        print(exc, file=sys.stderr)
+        return 2
+    for name, count in removed.items():
+        print(f"{name:<8} {count} removed")
+    return 0
+
+def _config_check() -> int:
+    """``openx config check``: report configuration problems; non-zero on errors."""
+    from .config import validate
+
+    problems = validate()
+    for level, message in problems:
+        print(f"{level}: {message}")
+    if not problems:
+        print("Configuration OK")
+    return 1 if any(level == "error" for level, _ in problems) else 0
+
 def main() -> None:
     """CLI entry point for the OpenX MCP server."""
+    from . import retention
+    from .config import validate as validate_config
+
     transport = "stdio"
     host = "127.0.0.1"
     port = int(os.environ.get("PORT", 8000))
 
     args = sys.argv[1:]
+    if args[:1] == ["paths"]:
+        _print_paths()
+        return
+    if args[:1] in (["trust"], ["untrust"]):
+        sys.exit(_trust_command(args))
+    if args[:2] == ["config", "check"]:
+        sys.exit(_config_check())
+    if args[:1] == ["purge"]:
+        sys.exit(_purge_command(args))
+    retention.apply_policy()
+    for level, message in validate_config():
+        (logger.error if level == "error" else logger.warning)("config: %s", message)
     i = 0
     while i < len(args):
         arg = args[i]
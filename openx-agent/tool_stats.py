@@ -0,0 +1,114 @@
+"""In-process accounting for tool calls.
+
+Every registered tool is wrapped so its (synchronous) body runs on a worker
+thread instead of blocking the event loop, and so the server can report how
+many calls are queued or running — letting clients tell "down" from "busy".
+Completed calls also feed per-tool latency and error-rate statistics.
+"""
+
+from __future__ import annotations
+
+import functools
+import inspect
+import threading
+import time
+from collections import Counter, defaultdict, deque
+from typing import Any, Callable
+
+import anyio.to_thread
+
+_lock = threading.Lock()
+_started = time.monotonic()
+_queued: Counter[str] = Counter()
+_running: Counter[str] = Counter()
+_calls: Counter[str] = Counter()
+_errors: Counter[str] = Counter()
+# Latencies (seconds) of the most recent calls per tool, for percentiles.
+_latencies: defaultdict[str, deque[float]] = defaultdict(lambda: deque(maxlen=200))
+
+def _move(name: str, src: Counter[str] | None, dst: Counter[str] | None) -> None:
+    with _lock:
+        if src is not None:
+            src[name] -= 1
+            if src[name] <= 0:
+                del src[name]
+        if dst is not None:
+            dst[name] += 1
+
+def _record(name: str, elapsed: float, failed: bool) -> None:
+    with _lock:
+        _calls[name] += 1
+        if failed:
+            _errors[name] += 1
+        _latencies[name].append(elapsed)
+
+def instrument(name: str, fn: Callable[..., Any]) -> Callable[..., Any]:
+    """Wrap tool *fn* to run off the event loop and be counted under *name*."""
+    signature = inspect.signature(fn, eval_str=True)
+
+    @functools.wraps(fn)
+    async def wrapper(*args: Any, **kwargs: Any) -> Any:
+        started = False
+
+        def _run() -> Any:
+            nonlocal started
+            started = True
+            _move(name, _queued, _running)
+            t0 = time.monotonic()
+            failed = True
+            try:
+                result = fn(*args, **kwargs)
+                failed = isinstance(result, dict) and result.get("status") == "error"
+                return result
+            finally:
+                _move(name, _running, None)
+                _record(name, time.monotonic() - t0, failed)
+
+        _move(name, None, _queued)
+        try:
+            return await anyio.to_thread.run_sync(_run)
+        finally:
+            if not started:  # cancelled before a worker picked it up
+                _move(name, _queued, None)
+
+    # Resolved annotations so FastMCP builds the schema from *fn*, not the wrapper.
+    wrapper.__signature__ = signature  # type: ignore[attr-defined]
+    return wrapper
+
+def status() -> dict[str, Any]:
+    """Snapshot of queued/running tool calls and worker capacity."""
+    limiter = anyio.to_thread.current_default_thread_limiter()
+    with _lock:
+        queued = sum(_queued.values())
+        running = sum(_running.values())
+        running_by_tool = dict(_running)
+    return {
+        "uptime_s": round(time.monotonic() - _started, 1),
+        "workers": int(limiter.total_tokens),
+        "running": running,
+        "queued": queued,
+        "running_by_tool": running_by_tool,
+        "state": "busy" if queued else "ok",
+    }
+
+def tool_stats() -> list[dict[str, Any]]:
+    """Per-tool call count, error rate, and latency, slowest (by p95) first.
+
+    A call counts as failed when it raises or returns ``{"status": "error"}``;
+    latencies cover the last 200 calls of each tool.
+    """
+    with _lock:
+        rows = [(name, _calls[name], _errors[name], sorted(_latencies[name])) for name in _calls]
+    stats: list[dict[str, Any]] = []
+    for name, calls, errors, lat in rows:
+        stats.append({
+            "tool": name,
+            "calls": calls,
+            "errors": errors,
+            "error_rate": round(errors / calls, 3),
+            "avg_ms": round(sum(lat) / len(lat) * 1000, 1),
+            "p95_ms": round(lat[min(len(lat) - 1, int(len(lat) * 0.95))] * 1000, 1),
+            "max_ms": round(lat[-1] * 1000, 1),
+        })
+    stats.sort(key=lambda r: r["p95_ms"], reverse=True)
+    return stats
@@ -0,0 +1,40 @@
+"""MCP tool registrations.
+
+Every tool is registered with one of these annotations; the server derives
+"Mutates state" in tool help from them, and clients may use them to ask for
+confirmation before destructive calls.
+"""
+
+from __future__ import annotations
+
+import logging
+from typing import Callable
+
+import anyio.from_thread
+from mcp.server.fastmcp import Context
+from mcp.types import ToolAnnotations
+
+logger = logging.getLogger(__name__)
+
+READ_ONLY = ToolAnnotations(readOnlyHint=True)
+# Adds to GitHub or the workspace without overwriting or removing anything.
+ADDITIVE = ToolAnnotations(readOnlyHint=False, destructiveHint=False)
+# May overwrite, merge, close, push, or otherwise discard existing state, or
+# start workflows whose effects (deploys, releases) the server cannot see.
+DESTRUCTIVE = ToolAnnotations(readOnlyHint=False, destructiveHint=True)
+
+def progress_reporter(ctx: Context) -> Callable[[int, int, str], None]:
+    """Callback that sends MCP progress notifications for the current call.
+
+    Tools run on worker threads, so the notification is handed back to the
+    event loop.  It is a no-op unless the client asked for progress, and a
+    failure to send never fails the tool.
+    """
+
+    def report(step: int, total: int, message: str) -> None:
+        try:
+            anyio.from_thread.run(ctx.report_progress, step, total, message)
+        except Exception as exc:
+            logger.debug("Could not report progress: %s", exc)
+
+    return report
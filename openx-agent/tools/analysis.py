@@ -4,25 +4,31 @@ from __future__ import annotations
 
 from typing import Any
 
-from mcp.server.fastmcp import FastMCP
+from mcp.server.fastmcp import Context, FastMCP
 
 from ..analysis.ai_analysis import analyze_with_ai
 from ..analysis.architecture import summarize_architecture
 from ..analysis.format_report import format_analysis_report
 from ..analysis.static_analysis import analyze_static
 from ..config import config_settings
+from . import READ_ONLY, progress_reporter
 
-def analyze_repo(path: str = "") -> Any:
+def analyze_repo(ctx: Context, path: str = "") -> Any:
     """Run full code analysis: static findings, architecture summary, and AI review.
 
     Detects bugs, performance issues, duplicate code, AI-generated patterns,
     and provides actionable recommendations. Omit path to analyze the workspace root.
+    Each phase is reported as an MCP progress notification.
     """
     root = path or config_settings.workspace_root
+    progress = progress_reporter(ctx)
+    progress(1, 3, "Running static analysis")
     static_findings = analyze_static(root)
+    progress(2, 3, "Summarizing architecture")
     arch = summarize_architecture(root)
+    progress(3, 3, "Running AI review")
     ai = analyze_with_ai({"static_findings": static_findings, "architecture": arch})
     return format_analysis_report(root, static_findings, arch, ai)
 
 def register(mcp: FastMCP) -> None:
-    mcp.add_tool(analyze_repo, name="analysis_analyze_repo")
+    mcp.add_tool(analyze_repo, name="analysis_analyze_repo", annotations=READ_ONLY)
@@ -4,8 +4,9 @@ from __future__ import annotations
 
 from typing import Any
 
-from mcp.server.fastmcp import FastMCP
+from mcp.server.fastmcp import Context, FastMCP
 
+from ..cache import clear_caches as _clear_caches
 from ..config import resolve_repo
 from ..gh_cli import run_gh_command as _run_gh_command
 from ..github_client import (
@@ -16,25 +17,32 @@ from ..github_client import (
     comment_pr as _comment_pr,
     create_issue as _create_issue,
     create_pull as _create_pull,
+    create_release as _create_release,
     generate_fix_patch as _generate_fix_patch,
-    get_ci_logs as _get_ci_logs,
+    get_ci_logs_page as _get_ci_logs_page,
     get_failing_prs as _get_failing_prs,
     get_issue as _get_issue,
     get_pr as _get_pr,
     get_readme as _get_readme,
     get_workflow_run as _get_workflow_run,
     heal_failing_pr as _heal_failing_pr,
+    heal_history_summary as _heal_history_summary,
     list_issues as _list_issues,
     list_open_prs as _list_open_prs,
     list_repos as _list_repos,
+    list_stale as _list_stale,
     list_workflow_runs as _list_workflow_runs,
     list_workflows as _list_workflows,
     locate_code_context as _locate_code_context,
     merge_pr as _merge_pr,
+    release_context as _release_context,
     rerun_ci as _rerun_ci,
+    suggest_change as _suggest_change,
+    sweep_stale as _sweep_stale,
     trigger_workflow as _trigger_workflow,
     update_readme as _update_readme,
 )
+from . import ADDITIVE, DESTRUCTIVE, READ_ONLY, progress_reporter
 
 def list_repos(org: str | None = None) -> Any:
     """List repositories for the authenticated user or an organization."""
@@ -62,6 +70,23 @@ def comment_pr(repo_full_name: str, number: int, body: str) -> Any:
     """Add a comment to a pull request."""
     return _comment_pr(repo_full_name, number, body)
 
+def suggest_change(
+    repo_full_name: str,
+    number: int,
+    path: str,
+    line: int,
+    replacement: str,
+    start_line: int | None = None,
+    body: str = "",
+    dry_run: bool = False,
+) -> Any:
+    """Comment on a PR hunk with a GitHub ```suggestion block the author can commit in one click.
+
+    Replaces lines start_line..line (new-file numbering; omit start_line for a
+    single line) of path with replacement. Use dry_run=True to preview the comment.
+    """
+    return _suggest_change(repo_full_name, number, path, line, replacement, start_line, body, dry_run)
+
 def merge_pr(
     repo_full_name: str,
     number: int,
@@ -108,6 +133,54 @@ def close_issue(repo_full_name: str, number: int) -> Any:
     """Close an issue."""
     return _close_issue(repo_full_name, number)
 
+def list_stale(
+    repo_full_name: str,
+    days: int = 60,
+    kind: str = "all",
+    exempt_labels: list[str] | None = None,
+) -> Any:
+    """List open issues/PRs inactive for at least `days` days (kind: issues, prs, all).
+
+    Items carrying any of exempt_labels (e.g. ["pinned", "security"]) are skipped.
+    """
+    return _list_stale(repo_full_name, days, kind, exempt_labels)
+
+def sweep_stale(
+    repo_full_name: str,
+    numbers: list[int],
+    comment: str = "",
+    label: str = "",
+    close: bool = False,
+) -> Any:
+    """Batch-apply a templated comment, a label, and optionally close issues/PRs.
+
+    comment may use {author}, {number}, and {days} placeholders. Pick numbers
+    from github_list_stale. Returns a per-item summary.
+    """
+    return _sweep_stale(repo_full_name, numbers, comment, label, close)
+
+def release_context(repo_full_name: str, since_tag: str = "") -> Any:
+    """List PRs merged into the default branch since a tag, for drafting release notes.
+
+    Omit since_tag to use the latest release (or newest tag).
+    """
+    return _release_context(repo_full_name, since_tag)
+
+def create_release(
+    repo_full_name: str,
+    tag: str,
+    name: str = "",
+    body: str = "",
+    target: str = "",
+    draft: bool = False,
+    prerelease: bool = False,
+) -> Any:
+    """Create a tag and GitHub release with the given notes (body, Markdown).
+
+    target = branch or commit SHA to tag; omit for the default branch.
+    """
+    return _create_release(repo_full_name, tag, name, body, target, draft, prerelease)
+
 def list_workflows(repo_full_name: str) -> Any:
     """List GitHub Actions workflows for a repository."""
     return _list_workflows(repo_full_name)
@@ -143,16 +216,33 @@ def run_gh_command(command: str) -> Any:
     except (ValueError, TimeoutError, RuntimeError) as e:
         return {"status": "error", "message": str(e)}
 
+def refresh_cache() -> Any:
+    """Drop all cached GitHub responses (repos, PR/workflow lists, PR details, CI logs)."""
+    _clear_caches()
+    return {"status": "cleared"}
+
 def get_failing_prs(repo: str) -> Any:
     """List pull requests with failed CI workflows in a repository."""
     return _get_failing_prs(repo)
 
-def get_ci_logs(repo: str, workflow_run_id: int) -> Any:
-    """Fetch raw GitHub Actions logs for a workflow run."""
-    return _get_ci_logs(repo, workflow_run_id)
+def get_ci_logs(
+    repo: str,
+    workflow_run_id: int,
+    offset: int = 0,
+    max_chars: int = 100_000,
+) -> Any:
+    """Fetch raw GitHub Actions logs for a workflow run, one page at a time.
+
+    When next_offset is not null the logs continue: call again with offset=next_offset.
+    """
+    return _get_ci_logs_page(repo, workflow_run_id, offset, max_chars)
 
 def analyze_ci_failure(logs: str) -> Any:
-    """Analyze CI log text and return structured error info (error_type, file_hint, reason)."""
+    """Classify CI log text into a structured failure report.
+
+    Returns error_type, category (dependency/syntax/code/test/build/lint/unknown),
+    confidence, file_hint, reason, suspect_files, and the suggested next_step tool.
+    """
     return _analyze_ci_failure(logs)
 
 def locate_code_context(repo: str, error_context: dict[str, Any]) -> Any:
@@ -171,39 +261,59 @@ def rerun_ci(repo: str, workflow_run_id: int) -> Any:
     """Re-run a GitHub Actions workflow."""
     return _rerun_ci(repo, workflow_run_id)
 
-def heal_failing_pr(repo: str, pr_number: int | None = None) -> Any:
+def heal_failing_pr(ctx: Context, repo: str, pr_number: int | None = None) -> Any:
     """Auto-heal a failing PR end-to-end.
 
     Pipeline: detect failure -> fetch CI logs -> analyze error ->
     generate fix patch -> commit to PR branch -> re-run CI.
     If pr_number is omitted, heals the first failing PR found.
+    The repo's heal policy may stop after analysis (status 'analyzed') or
+    return the patch without applying it (status 'patch_ready'; apply it with
+    github_apply_fix_to_pr).
+    Each pipeline stage is reported as an MCP progress notification.
+    """
+    return _heal_failing_pr(repo, pr_number, progress_reporter(ctx))
+
+def heal_history(repo: str = "", recent: int = 20) -> Any:
+    """Summarize past heal attempts: success rate, outcomes, and error types per repo.
+
+    An attempt counts as healed only once its CI re-run passes; pending re-runs
+    are checked on GitHub each time this is called.
+    Omit repo to include every repository; recent = number of latest attempts to list.
     """
-    return _heal_failing_pr(repo, pr_number)
+    return _heal_history_summary(repo.strip() or None, recent)
 
 def register(mcp: FastMCP) -> None:
-    mcp.add_tool(list_repos, name="github_list_repos")
-    mcp.add_tool(list_prs, name="github_list_prs")
-    mcp.add_tool(get_pr, name="github_get_pr")
-    mcp.add_tool(create_pr, name="github_create_pr")
-    mcp.add_tool(comment_pr, name="github_comment_pr")
-    mcp.add_tool(merge_pr, name="github_merge_pr")
-    mcp.add_tool(get_readme, name="github_get_readme")
-    mcp.add_tool(update_readme, name="github_update_readme")
-    mcp.add_tool(list_issues, name="github_list_issues")
-    mcp.add_tool(get_issue, name="github_get_issue")
-    mcp.add_tool(create_issue, name="github_create_issue")
-    mcp.add_tool(comment_issue, name="github_comment_issue")
-    mcp.add_tool(close_issue, name="github_close_issue")
-    mcp.add_tool(list_workflows, name="github_list_workflows")
-    mcp.add_tool(trigger_workflow, name="github_trigger_workflow")
-    mcp.add_tool(list_workflow_runs, name="github_list_workflow_runs")
-    mcp.add_tool(get_workflow_run, name="github_get_workflow_run")
-    mcp.add_tool(run_gh_command, name="github_run_gh_command")
-    mcp.add_tool(get_failing_prs, name="github_get_failing_prs")
-    mcp.add_tool(get_ci_logs, name="github_get_ci_logs")
-    mcp.add_tool(analyze_ci_failure, name="github_analyze_ci_failure")
-    mcp.add_tool(locate_code_context, name="github_locate_code_context")
-    mcp.add_tool(generate_fix_patch, name="github_generate_fix_patch")
-    mcp.add_tool(apply_fix_to_pr, name="github_apply_fix_to_pr")
-    mcp.add_tool(rerun_ci, name="github_rerun_ci")
-    mcp.add_tool(heal_failing_pr, name="github_heal_failing_pr")
+    mcp.add_tool(list_repos, name="github_list_repos", annotations=READ_ONLY)
+    mcp.add_tool(list_prs, name="github_list_prs", annotations=READ_ONLY)
+    mcp.add_tool(get_pr, name="github_get_pr", annotations=READ_ONLY)
+    mcp.add_tool(create_pr, name="github_create_pr", annotations=ADDITIVE)
+    mcp.add_tool(comment_pr, name="github_comment_pr", annotations=ADDITIVE)
+    mcp.add_tool(suggest_change, name="github_suggest_change", annotations=ADDITIVE)
+    mcp.add_tool(merge_pr, name="github_merge_pr", annotations=DESTRUCTIVE)
+    mcp.add_tool(get_readme, name="github_get_readme", annotations=READ_ONLY)
+    mcp.add_tool(update_readme, name="github_update_readme", annotations=DESTRUCTIVE)
+    mcp.add_tool(list_issues, name="github_list_issues", annotations=READ_ONLY)
+    mcp.add_tool(get_issue, name="github_get_issue", annotations=READ_ONLY)
+    mcp.add_tool(create_issue, name="github_create_issue", annotations=ADDITIVE)
+    mcp.add_tool(comment_issue, name="github_comment_issue", annotations=ADDITIVE)
+    mcp.add_tool(close_issue, name="github_close_issue", annotations=DESTRUCTIVE)
+    mcp.add_tool(list_stale, name="github_list_stale", annotations=READ_ONLY)
+    mcp.add_tool(sweep_stale, name="github_sweep_stale", annotations=DESTRUCTIVE)
+    mcp.add_tool(release_context, name="github_release_context", annotations=READ_ONLY)
+    mcp.add_tool(create_release, name="github_create_release", annotations=ADDITIVE)
+    mcp.add_tool(list_workflows, name="github_list_workflows", annotations=READ_ONLY)
+    mcp.add_tool(trigger_workflow, name="github_trigger_workflow", annotations=DESTRUCTIVE)
+    mcp.add_tool(list_workflow_runs, name="github_list_workflow_runs", annotations=READ_ONLY)
+    mcp.add_tool(get_workflow_run, name="github_get_workflow_run", annotations=READ_ONLY)
+    mcp.add_tool(run_gh_command, name="github_run_gh_command", annotations=DESTRUCTIVE)
+    mcp.add_tool(refresh_cache, name="github_refresh_cache", annotations=READ_ONLY)
+    mcp.add_tool(get_failing_prs, name="github_get_failing_prs", annotations=READ_ONLY)
+    mcp.add_tool(get_ci_logs, name="github_get_ci_logs", annotations=READ_ONLY)
+    mcp.add_tool(analyze_ci_failure, name="github_analyze_ci_failure", annotations=READ_ONLY)
+    mcp.add_tool(locate_code_context, name="github_locate_code_context", annotations=READ_ONLY)
+    mcp.add_tool(generate_fix_patch, name="github_generate_fix_patch", annotations=READ_ONLY)
+    mcp.add_tool(apply_fix_to_pr, name="github_apply_fix_to_pr", annotations=DESTRUCTIVE)
+    mcp.add_tool(rerun_ci, name="github_rerun_ci", annotations=ADDITIVE)
+    mcp.add_tool(heal_failing_pr, name="github_heal_failing_pr", annotations=DESTRUCTIVE)
+    mcp.add_tool(heal_history, name="github_heal_history", annotations=READ_ONLY)
@@ -7,22 +7,37 @@ from typing import Any
 from mcp.server.fastmcp import FastMCP
 
 from ..workspace import (
+    apply_patch_in_worktree as _apply_patch_in_worktree,
+    commit_context as _commit_context,
+    find_symbol as _find_symbol,
     git_add as _git_add,
     git_commit as _git_commit,
     git_push as _git_push,
+    git_stash as _git_stash,
+    git_stash_list as _git_stash_list,
+    git_stash_pop as _git_stash_pop,
     git_status as _git_status,
+    grep as _grep,
     list_dir as _list_dir,
     read_file as _read_file,
     write_file as _write_file,
 )
+from . import ADDITIVE, DESTRUCTIVE, READ_ONLY
 
-def read_file(repo_path: str = "", path: str = "README.md") -> Any:
+def read_file(
+    repo_path: str = "",
+    path: str = "README.md",
+    start_line: int | None = None,
+    end_line: int | None = None,
+) -> Any:
     """Read a file from the local workspace.
 
     repo_path: subdirectory of the workspace root (empty for root).
     path: file path relative to repo_path.
+    start_line/end_line: optional 1-based inclusive range, e.g. to check a cited snippet;
+    an inverted range or a start past the end of the file is an error.
     """
-    return _read_file(repo_path, path or "README.md")
+    return _read_file(repo_path, path or "README.md", start_line, end_line)
 
 def write_file(content: str, repo_path: str = "", path: str = "README.md") -> Any:
     """Write content to a file in the workspace, creating parent directories as needed."""
@@ -32,6 +47,23 @@ def list_dir(repo_path: str = "", subdir: str = "") -> Any:
     """List files and directories in the workspace."""
     return _list_dir(repo_path, subdir)
 
+def grep(
+    pattern: str,
+    repo_path: str = "",
+    glob: str = "",
+    ignore_case: bool = False,
+    max_results: int = 200,
+) -> Any:
+    """Search workspace files with a regular expression; results are grouped by file with line numbers.
+
+    glob: optional file-name filter such as '*.py'.
+    """
+    return _grep(repo_path, pattern, glob, ignore_case, max_results)
+
+def find_symbol(name: str, repo_path: str = "", max_results: int = 50) -> Any:
+    """Find where a function, class, or type is defined in the workspace (path, line, kind)."""
+    return _find_symbol(repo_path, name, max_results)
+
 def git_status(repo_path: str = "") -> Any:
     """Show git status and diff stat for the local repository."""
     return _git_status(repo_path)
@@ -44,6 +76,14 @@ def git_commit(message: str, repo_path: str = "") -> Any:
     """Commit staged changes. Use conventional messages: fix:, feat:, refactor:, etc."""
     return _git_commit(repo_path, message)
 
+def commit_context(repo_path: str = "", recent: int | None = None) -> Any:
+    """Get the staged diff, the last `recent` commit subjects, and the repo's commit style.
+
+    Use it to draft a message that matches the project's conventions before
+    workspace_git_commit. The style comes from [commit] in .openx.toml when set.
+    """
+    return _commit_context(repo_path, recent)
+
 def git_push(
     repo_path: str = "",
     remote: str = "origin",
@@ -52,11 +92,50 @@ def git_push(
     """Push commits to a remote. Uses the current branch when branch is omitted."""
     return _git_push(repo_path, remote, branch)
 
+def git_stash(
+    repo_path: str = "",
+    message: str | None = None,
+    include_untracked: bool = False,
+) -> Any:
+    """Stash uncommitted changes. Set include_untracked to also stash new files."""
+    return _git_stash(repo_path, message, include_untracked)
+
+def git_stash_list(repo_path: str = "") -> Any:
+    """List stashes (newest first) with their index and message."""
+    return _git_stash_list(repo_path)
+
+def git_stash_pop(repo_path: str = "", index: int = 0) -> Any:
+    """Apply and drop a stash. index 0 is the most recent stash."""
+    return _git_stash_pop(repo_path, index)
+
+def apply_patch_safely(
+    patch: str,
+    test_command: str = "",
+    message: str = "fix: apply patch",
+    repo_path: str = "",
+) -> Any:
+    """Apply a unified diff in a temporary git worktree, run tests there, and
+    fast-forward the current branch only if they pass.
+
+    test_command: e.g. 'pytest -q'; omit to only check that the patch applies.
+    Test commands only run in workspaces the user has trusted with 'openx trust'.
+    The working tree is left untouched when the patch or tests fail, and when
+    uncommitted changes touch the patched files (status 'dirty_overlap').
+    """
+    return _apply_patch_in_worktree(repo_path, patch, test_command, message)
+
 def register(mcp: FastMCP) -> None:
-    mcp.add_tool(read_file, name="workspace_read_file")
-    mcp.add_tool(write_file, name="workspace_write_file")
-    mcp.add_tool(list_dir, name="workspace_list_dir")
-    mcp.add_tool(git_status, name="workspace_git_status")
-    mcp.add_tool(git_add, name="workspace_git_add")
-    mcp.add_tool(git_commit, name="workspace_git_commit")
-    mcp.add_tool(git_push, name="workspace_git_push")
+    mcp.add_tool(read_file, name="workspace_read_file", annotations=READ_ONLY)
+    mcp.add_tool(write_file, name="workspace_write_file", annotations=DESTRUCTIVE)
+    mcp.add_tool(list_dir, name="workspace_list_dir", annotations=READ_ONLY)
+    mcp.add_tool(grep, name="workspace_grep", annotations=READ_ONLY)
+    mcp.add_tool(find_symbol, name="workspace_find_symbol", annotations=READ_ONLY)
+    mcp.add_tool(git_status, name="workspace_git_status", annotations=READ_ONLY)
+    mcp.add_tool(git_add, name="workspace_git_add", annotations=ADDITIVE)
+    mcp.add_tool(commit_context, name="workspace_commit_context", annotations=READ_ONLY)
+    mcp.add_tool(git_commit, name="workspace_git_commit", annotations=ADDITIVE)
+    mcp.add_tool(git_push, name="workspace_git_push", annotations=DESTRUCTIVE)
+    mcp.add_tool(git_stash, name="workspace_git_stash", annotations=ADDITIVE)
+    mcp.add_tool(git_stash_list, name="workspace_git_stash_list", annotations=READ_ONLY)
+    mcp.add_tool(git_stash_pop, name="workspace_git_stash_pop", annotations=DESTRUCTIVE)
+    mcp.add_tool(apply_patch_safely, name="workspace_apply_patch_safely", annotations=DESTRUCTIVE)
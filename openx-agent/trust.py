@@ -0,0 +1,80 @@
+"""Workspace trust for features that execute local commands.
+
+Running a test command from agent output, and honouring a project's
+``.openx.toml``, are only allowed inside a directory the user has explicitly
+trusted (``openx trust <dir>``), so a crafted repository cannot trigger local
+execution or reconfigure the server just by being opened.  Trusting a
+directory also trusts everything below it.
+"""
+
+from __future__ import annotations
+
+import json
+import logging
+import threading
+from pathlib import Path
+
+logger = logging.getLogger(__name__)
+_TRUST_FILE = "trusted_workspaces.json"
+_lock = threading.Lock()
+
+def _path(data_dir: str | None = None) -> Path:
+    if data_dir is None:
+        from .config import settings  # deferred: config consults trust while loading
+
+        data_dir = settings.data_dir
+    return Path(data_dir).expanduser() / _TRUST_FILE
+
+def trusted(data_dir: str | None = None) -> list[str]:
+    """Return the trusted directories, as recorded."""
+    path = _path(data_dir)
+    if not path.is_file():
+        return []
+    try:
+        with _lock:
+            data = json.loads(path.read_text(encoding="utf-8"))
+    except (OSError, json.JSONDecodeError) as exc:
+        logger.warning("Ignoring unreadable %s: %s", path, exc)
+        return []
+    return [p for p in data.get("trusted", []) if isinstance(p, str)]
+
+def _save(entries: list[str]) -> None:
+    path = _path()
+    with _lock:
+        path.parent.mkdir(parents=True, exist_ok=True)
+        path.write_text(json.dumps({"trusted": sorted(set(entries))}, indent=2) + "\n", encoding="utf-8")
+
+def trust(directory: str | Path) -> str:
+    """Record *directory* as trusted and return its resolved path."""
+    resolved = str(Path(directory).expanduser().resolve())
+    entries = trusted()
+    if resolved not in entries:
+        _save([*entries, resolved])
+    return resolved
+
+def untrust(directory: str | Path) -> bool:
+    """Remove *directory* from the trusted list; False if it was not listed."""
+    resolved = str(Path(directory).expanduser().resolve())
+    entries = trusted()
+    if resolved not in entries:
+        return False
+    _save([e for e in entries if e != resolved])
+    return True
+
+def is_trusted(directory: str | Path, data_dir: str | None = None) -> bool:
+    resolved = Path(directory).expanduser().resolve()
+    for entry in trusted(data_dir):
+        try:
+            resolved.relative_to(entry)
+            return True
+        except ValueError:
+            continue
+    return False
+
+def require(directory: str | Path) -> None:
+    """Raise PermissionError unless *directory* is trusted."""
+    if not is_trusted(directory):
+        raise PermissionError(
+            f"Workspace {directory} is not trusted for local command execution."
+            f" Run 'openx trust {directory}' to allow it."
+        )
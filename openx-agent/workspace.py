@@ -6,10 +6,18 @@ agents cannot escape the sandbox via path traversal.
 
 from __future__ import annotations
 
+import fnmatch
+import os
+import re
+import shlex
+import shutil
 import subprocess
+import tempfile
 from pathlib import Path
-from typing import Any
+from typing import Any, Iterator
 
+from . import sandbox, trust
+from .analysis.static_analysis import CODE_EXTENSIONS, SKIP_DIRS
 from .config import settings
 
 def _root() -> Path:
@@ -32,12 +40,34 @@ def _resolve(repo_path: str, *parts: str) -> Path:
         raise PermissionError(f"Path must be under workspace root: {root}")
     return resolved
 
-def read_file(repo_path: str, path: str) -> str:
-    """Read *path* (relative to *repo_path*) from the local workspace."""
+def read_file(
+    repo_path: str,
+    path: str,
+    start_line: int | None = None,
+    end_line: int | None = None,
+) -> str:
+    """Read *path* (relative to *repo_path*) from the local workspace.
+
+    *start_line*/*end_line* (1-based, inclusive) restrict the result to a line range;
+    an *end_line* past the end of the file is clamped.  Raises ValueError for a
+    range that selects no lines.
+    """
     full = _resolve(repo_path, path)
     if not full.is_file():
         raise FileNotFoundError(f"Not a file: {path}")
-    return full.read_text(encoding="utf-8", errors="replace")
+    text = full.read_text(encoding="utf-8", errors="replace")
+    if start_line is None and end_line is None:
+        return text
+    lines = text.splitlines(keepends=True)
+    start = 1 if start_line is None else start_line
+    end = len(lines) if end_line is None else end_line
+    if start < 1 or end < 1:
+        raise ValueError("start_line and end_line must be at least 1")
+    if start > len(lines):
+        raise ValueError(f"start_line ({start}) is past the end of {path} ({len(lines)} lines)")
+    if start > end:
+        raise ValueError(f"start_line ({start}) is after end_line ({end})")
+    return "".join(lines[start - 1:min(end, len(lines))])
 
 def write_file(repo_path: str, path: str, content: str) -> dict[str, Any]:
     """Write *content* to *path*, creating parent directories as needed."""
@@ -49,19 +79,203 @@ def write_file(repo_path: str, path: str, content: str) -> dict[str, Any]:
 def list_dir(repo_path: str, subdir: str = "") -> list[dict[str, Any]]:
     """List files and directories under *repo_path/subdir*.
 
-    Hidden entries (dot-files) are omitted except ``.git``.
+    Hidden entries (dot-files) are omitted except ``.git``, as is anything
+    matched by the repo's ``.gitignore`` / ``.openxignore``.
     """
+    top = _resolve(repo_path)
     full = _resolve(repo_path, subdir)
     if not full.is_dir():
         raise NotADirectoryError(f"Not a directory: {subdir or repo_path or '.'}")
+    patterns = _ignore_patterns(top)
     return [
         {"name": p.name, "type": "dir" if p.is_dir() else "file"}
         for p in sorted(full.iterdir())
-        if not p.name.startswith(".") or p.name == ".git"
+        if (not p.name.startswith(".") or p.name == ".git")
+        and not _is_ignored(p.relative_to(top).as_posix(), p.is_dir(), patterns)
     ]
 
+IGNORE_FILES = (".gitignore", ".openxignore")
+MAX_SCAN_BYTES = 1_000_000
+
+def _ignore_patterns(top: Path) -> list[str]:
+    """Patterns from ``.gitignore`` and ``.openxignore`` in *top*.
+
+    Supports the common subset of gitignore syntax: globs, ``dir/`` suffixes,
+    and ``/``-anchored paths.  Negations (``!``) are not supported and skipped.
+    """
+    patterns: list[str] = []
+    for name in IGNORE_FILES:
+        try:
+            lines = (top / name).read_text(encoding="utf-8", errors="replace").splitlines()
+        except OSError:
+            continue
+        patterns.extend(
+            line.strip() for line in lines
+            if line.strip() and not line.lstrip().startswith(("#", "!"))
+        )
+    return patterns
+
+def _is_ignored(rel: str, is_dir: bool, patterns: list[str]) -> bool:
+    """Whether *rel* (posix path relative to the repo root) matches an ignore pattern."""
+    name = rel.rsplit("/", 1)[-1]
+    for pat in patterns:
+        dir_only = pat.endswith("/")
+        pat = pat.rstrip("/")
+        if dir_only and not is_dir:
+            continue
+        if "/" in pat:
+            if fnmatch.fnmatch(rel, pat.lstrip("/")):
+                return True
+        elif fnmatch.fnmatch(name, pat):
+            return True
+    return False
+
+def _is_binary(path: Path) -> bool:
+    try:
+        with open(path, "rb") as fh:
+            return b"\0" in fh.read(8192)
+    except OSError:
+        return True
+
+def _iter_files(top: Path, glob: str = "") -> Iterator[Path]:
+    """Yield scannable text files under *top*.
+
+    Skips VCS/dependency/build directories, ignore-file matches, symlinks
+    that resolve outside the workspace root, files larger than
+    ``MAX_SCAN_BYTES``, and binary files.
+    """
+    root = _root()
+    patterns = _ignore_patterns(top)
+    for base, dirs, files in os.walk(top):
+        rel_base = Path(base).relative_to(top).as_posix()
+        prefix = "" if rel_base == "." else rel_base + "/"
+        dirs[:] = sorted(
+            d for d in dirs
+            if d not in SKIP_DIRS and not d.endswith(".egg-info")
+            and not _is_ignored(prefix + d, True, patterns)
+        )
+        for name in sorted(files):
+            if glob and not fnmatch.fnmatch(name, glob):
+                continue
+            if _is_ignored(prefix + name, False, patterns):
+                continue
+            path = Path(base) / name
+            try:
+                path.resolve().relative_to(root)  # same guard as _resolve()
+            except (OSError, ValueError):
+                continue
+            try:
+                if path.stat().st_size > MAX_SCAN_BYTES:
+                    continue
+            except OSError:
+                continue
+            if _is_binary(path):
+                continue
+            yield path
+
+def grep(
+    repo_path: str,
+    pattern: str,
+    glob: str = "",
+    ignore_case: bool = False,
+    max_results: int = 200,
+) -> dict[str, Any]:
+    """Regex search across workspace files, grouped by file.
+
+    *glob* filters by file name (e.g. ``*.py``).  Stops after *max_results* matching lines.
+    """
+    if max_results < 1:
+        raise ValueError("max_results must be at least 1")
+    try:
+        regex = re.compile(pattern, re.IGNORECASE if ignore_case else 0)
+    except re.error as exc:
+        raise ValueError(f"Invalid pattern: {exc}")
+    top = _resolve(repo_path)
+    results: list[dict[str, Any]] = []
+    total = 0
+    truncated = False
+    for path in _iter_files(top, glob):
+        try:
+            with open(path, encoding="utf-8", errors="replace") as fh:
+                hits = [
+                    {"line": i, "text": line.rstrip("\n")[:500]}
+                    for i, line in enumerate(fh, start=1)
+                    if regex.search(line)
+                ]
+        except OSError:
+            continue
+        if not hits:
+            continue
+        if total + len(hits) > max_results:
+            hits = hits[: max_results - total]
+            truncated = True
+        if hits:
+            results.append({"path": str(path.relative_to(top)), "matches": hits})
+            total += len(hits)
+        if truncated:
+            break
+    return {"pattern": pattern, "total": total, "truncated": truncated, "files": results}
+
+# Definition patterns per extension; ``{name}`` is replaced with the escaped symbol.
+_SYMBOL_PATTERNS: dict[str, list[tuple[str, str]]] = {
+    ".py": [(r"^\s*(?:async\s+)?def\s+{name}\b", "function"), (r"^\s*class\s+{name}\b", "class"),
+            (r"^{name}\s*(?::[^=]+)?=", "variable")],
+    ".js": [(r"\bfunction\s*\*?\s*{name}\b", "function"), (r"\bclass\s+{name}\b", "class"),
+            (r"\b(?:const|let|var)\s+{name}\s*=", "variable")],
+    ".go": [(r"^func\s+(?:\([^)]*\)\s*)?{name}\b", "function"), (r"^type\s+{name}\b", "type")],
+    ".rs": [(r"\bfn\s+{name}\b", "function"), (r"\b(?:struct|enum|trait|type)\s+{name}\b", "type"),
+            (r"\b(?:const|static)\s+{name}\b", "variable")],
+    ".java": [(r"\b(?:class|interface|enum|record)\s+{name}\b", "class"),
+              (r"^\s*(?:public|private|protected|static|final|\s)+[\w<>\[\],\s]+\s+{name}\s*\(", "method")],
+    ".rb": [(r"^\s*def\s+(?:self\.)?{name}\b", "function"), (r"^\s*(?:class|module)\s+{name}\b", "class")],
+    ".c": [(r"^[\w\*\s]+\b{name}\s*\([^;]*$", "function"), (r"\b(?:struct|enum|union)\s+{name}\b", "type"),
+           (r"#define\s+{name}\b", "macro")],
+}
+for _ext, _alias in ((".ts", ".js"), (".tsx", ".js"), (".jsx", ".js"), (".cs", ".java"),
+                     (".h", ".c"), (".cpp", ".c"), (".hpp", ".c")):
+    _SYMBOL_PATTERNS[_ext] = _SYMBOL_PATTERNS[_alias]
+
+def find_symbol(repo_path: str, name: str, max_results: int = 50) -> list[dict[str, Any]]:
+    """Locate definitions of *name* (functions, classes, types) across code files.
+
+    A lightweight, ctags-style regex pass — no index is built or kept.
+    """
+    if not re.fullmatch(r"[A-Za-z_][A-Za-z0-9_]*", name or ""):
+        raise ValueError("Symbol name must be a plain identifier")
+    compiled = {
+        ext: [(re.compile(pat.replace("{name}", re.escape(name))), kind) for pat, kind in pats]
+        for ext, pats in _SYMBOL_PATTERNS.items()
+    }
+    top = _resolve(repo_path)
+    found: list[dict[str, Any]] = []
+    for path in _iter_files(top):
+        ext = path.suffix.lower()
+        if ext not in CODE_EXTENSIONS or ext not in compiled:
+            continue
+        try:
+            with open(path, encoding="utf-8", errors="replace") as fh:
+                for i, line in enumerate(fh, start=1):
+                    if name not in line:
+                        continue
+                    kind = next((k for rx, k in compiled[ext] if rx.search(line)), None)
+                    if kind:
+                        found.append({
+                            "path": str(path.relative_to(top)),
+                            "line": i,
+                            "kind": kind,
+                            "text": line.strip()[:300],
+                        })
+                        if len(found) >= max_results:
+                            return found
+        except OSError:
+            continue
+    return found
+
 def _git(repo_path: str, *args: str) -> str:
-    cwd = _resolve(repo_path)
+    return _git_in(_resolve(repo_path), *args)
+
+def _git_in(cwd: Path, *args: str) -> str:
+    """Run git in an already-validated directory (e.g. a temporary worktree)."""
     try:
         r = subprocess.run(
             ["git", *args],
@@ -71,7 +285,9 @@ def _git(repo_path: str, *args: str) -> str:
             timeout=60,
         )
         if r.returncode != 0:
-            raise RuntimeError(f"git {' '.join(args)} failed: {r.stderr.strip()}")
+            # Some failures (e.g. stash pop conflicts) are only reported on stdout.
+            detail = r.stderr.strip() or r.stdout.strip()
+            raise RuntimeError(f"git {' '.join(args)} failed: {detail}")
         return r.stdout.strip()
     except subprocess.TimeoutExpired:
         raise TimeoutError(f"git {' '.join(args)} timed out")
@@ -94,6 +310,35 @@ def git_commit(repo_path: str, message: str) -> dict[str, Any]:
     output = _git(repo_path, "commit", "-m", message)
     return {"message": message, "output": output}
 
+_CONVENTIONAL_RE = re.compile(r"^(feat|fix|docs|style|refactor|perf|test|build|ci|chore|revert)(\([^)]*\))?!?: ")
+_MAX_COMMIT_DIFF = 20_000
+
+def commit_context(repo_path: str = "", recent: int | None = None) -> dict[str, Any]:
+    """Staged diff plus recent commit subjects, for drafting a matching commit message.
+
+    ``style`` is the ``[commit] style`` override from .openx.toml when set,
+    otherwise ``conventional`` or ``freeform`` inferred from recent subjects.
+    """
+    table = settings.project.get("commit") or {}
+    count = recent if recent is not None else int(table.get("recent", 20))
+    stat = _git(repo_path, "diff", "--cached", "--stat")
+    diff = _git(repo_path, "diff", "--cached")
+    try:
+        log = _git(repo_path, "log", f"-{max(count, 0)}", "--format=%s")
+    except RuntimeError:  # no commits yet
+        log = ""
+    subjects = log.splitlines()
+    conventional = sum(1 for s in subjects if _CONVENTIONAL_RE.match(s))
+    inferred = "conventional" if subjects and conventional * 2 > len(subjects) else "freeform"
+    truncated = len(diff) > _MAX_COMMIT_DIFF
+    return {
+        "staged_stat": stat,
+        "staged_diff": diff[:_MAX_COMMIT_DIFF] + ("\n[... diff truncated ...]" if truncated else ""),
+        "recent_subjects": subjects,
+        "style": table.get("style") or inferred,
+        "style_source": "config" if table.get("style") else "inferred",
+    }
+
 def git_push(repo_path: str, remote: str = "origin", branch: str | None = None) -> dict[str, Any]:
     """Push to *remote*.  Uses the current branch when *branch* is omitted."""
     args = ["push", remote]
@@ -102,6 +347,31 @@ def git_push(repo_path: str, remote: str = "origin", branch: str | None = None)
     output = _git(repo_path, *args)
     return {"remote": remote, "branch": branch, "output": output}
 
+def git_stash(repo_path: str = "", message: str | None = None, include_untracked: bool = False) -> dict[str, Any]:
+    """Stash local changes.  Untracked files are only stashed when *include_untracked* is set."""
+    args = ["stash", "push"]
+    if include_untracked:
+        args.append("--include-untracked")
+    if message:
+        args.extend(["-m", message])
+    output = _git(repo_path, *args)
+    return {"stashed": not output.startswith("No local changes"), "output": output}
+
+def git_stash_list(repo_path: str = "") -> list[dict[str, Any]]:
+    """List stashes, newest first, as ``{"index", "ref", "message"}`` entries."""
+    output = _git(repo_path, "stash", "list", "--format=%gd%x09%s")
+    entries: list[dict[str, Any]] = []
+    for i, line in enumerate(output.splitlines()):
+        ref, _, message = line.partition("\t")
+        entries.append({"index": i, "ref": ref, "message": message})
+    return entries
+
+def git_stash_pop(repo_path: str = "", index: int = 0) -> dict[str, Any]:
+    """Apply and drop stash ``stash@{index}``."""
+    ref = f"stash@{{{index}}}"
+    output = _git(repo_path, "stash", "pop", ref)
+    return {"ref": ref, "output": output}
+
 def git_current_branch(repo_path: str = "") -> str:
     """Return the current branch name (``HEAD`` if detached)."""
     return _git(repo_path, "rev-parse", "--abbrev-ref", "HEAD") or "HEAD"
@@ -112,3 +382,79 @@ def git_remote_url(repo_path: str = "", remote: str = "origin") -> str:
         return _git(repo_path, "remote", "get-url", remote)
     except RuntimeError:
         return ""
+
+def apply_patch_in_worktree(
+    repo_path: str,
+    patch: str,
+    test_command: str = "",
+    message: str = "fix: apply patch",
+) -> dict[str, Any]:
+    """Apply *patch* in a throwaway ``git worktree``, run *test_command* there,
+    and fast-forward the current branch only if the tests pass.
+
+    The caller's working tree is never touched unless the fast-forward happens;
+    uncommitted changes are stashed around it and restored afterwards.
+    Running *test_command* requires the repository to be trusted (``openx trust``).
+    """
+    if not patch.strip():
+        raise ValueError("Patch is empty")
+    repo = _resolve(repo_path)
+    if test_command:
+        trust.require(repo)
+    branch = git_current_branch(repo_path)
+    tmp = Path(tempfile.mkdtemp(prefix="openx-worktree-"))
+    worktree = tmp / "wt"
+    try:
+        _git(repo_path, "worktree", "add", "--detach", str(worktree), "HEAD")
+        patch_file = tmp / "fix.patch"
+        patch_file.write_text(patch if patch.endswith("\n") else patch + "\n", encoding="utf-8")
+        try:
+            _git_in(worktree, "apply", "--index", str(patch_file))
+        except RuntimeError as exc:
+            return {"status": "patch_failed", "branch": branch, "message": str(exc)}
+        _git_in(worktree, "commit", "-m", message)
+        commit = _git_in(worktree, "rev-parse", "HEAD")
+
+        result: dict[str, Any] = {"branch": branch, "commit": commit, "test_command": test_command or None}
+        if test_command:
+            try:
+                run = sandbox.run(shlex.split(test_command), worktree)
+            except OSError as exc:
+                return {**result, "status": "tests_failed", "output": str(exc)}
+            result["output"] = run["output"]
+            result["truncated_bytes"] = run["truncated_bytes"]
+            if run["timed_out"]:
+                return {**result, "status": "tests_timed_out"}
+            if run["exit_code"] != 0:
+                return {**result, "status": "tests_failed", "exit_code": run["exit_code"]}
+
+        touched = set(_git_in(worktree, "diff", "--name-only", "HEAD~1", "HEAD").splitlines())
+        dirty = set(_git_in(repo, "diff", "--name-only", "HEAD").splitlines())
+        if touched & dirty:
+            return {
+                **result,
+                "status": "dirty_overlap",
+                "files": sorted(touched & dirty),
+                "message": "Uncommitted changes touch the patched files; commit or stash them and retry.",
+            }
+        stashed = git_stash(repo_path, f"openx: before applying {commit[:12]}")["stashed"]
+        try:
+            _git_in(repo, "merge", "--ff-only", commit)
+        except RuntimeError as exc:
+            result = {**result, "status": "fast_forward_failed", "message": str(exc)}
+        else:
+            result = {**result, "status": "applied"}
+        if stashed:
+            try:
+                git_stash_pop(repo_path)
+            except RuntimeError as exc:
+                # Undo the partial pop; the changes stay in the stash.
+                _git_in(repo, "reset", "--merge")
+                result["stash"] = f"Could not restore local changes; they are kept in stash@{{0}}. {exc}"
+        return result
+    finally:
+        try:
+            _git(repo_path, "worktree", "remove", "--force", str(worktree))
+        except (RuntimeError, TimeoutError):
+            pass
+        shutil.rmtree(tmp, ignore_errors=True)